@@ -0,0 +1,722 @@
+use std::collections::HashMap;
+
+use crate::interpreter::Dispatch;
+use crate::parser::Node;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub position: usize,
+    pub message: String,
+}
+
+struct Handler<'a> {
+    args: &'a Vec<Node>,
+    guard: &'a Option<Box<Node>>,
+    position: usize,
+}
+
+// how specific a pattern is, literal over class pattern over wildcard
+fn pattern_rank(pattern: &Node) -> u8 {
+    match pattern {
+        Node::IdLookup(_) | Node::Spread(_) => 0,
+        Node::RecordConstructor(_, _)
+        | Node::VectorConstructor(_, _)
+        | Node::RecordLiteral(_)
+        | Node::Dict(_)
+        | Node::List(_) => 1,
+        Node::As(_, pattern) | Node::ParenExpr(pattern) => pattern_rank(pattern),
+        Node::Or(alternatives) => alternatives.iter().map(pattern_rank).min().unwrap_or(0),
+        _ => 2,
+    }
+}
+
+// how many parts of a pattern aren't wildcards, so `[1; 2;]` beats `[x; ...rest;]`
+fn pattern_detail(pattern: &Node) -> usize {
+    match pattern {
+        Node::IdLookup(_) => 0,
+        Node::Spread(node) | Node::As(_, node) | Node::ParenExpr(node) => pattern_detail(node),
+        Node::Or(alternatives) => alternatives.iter().map(pattern_detail).min().unwrap_or(0),
+        Node::List(nodes) | Node::VectorConstructor(_, nodes) => {
+            1 + nodes.iter().map(pattern_detail).sum::<usize>()
+        }
+        Node::RecordConstructor(_, props) | Node::RecordLiteral(props) => {
+            1 + props
+                .iter()
+                .map(|(_, node)| pattern_detail(node))
+                .sum::<usize>()
+        }
+        Node::Dict(entries) => {
+            1 + entries
+                .iter()
+                .map(|(_, node)| pattern_detail(node))
+                .sum::<usize>()
+        }
+        _ => 1,
+    }
+}
+
+// compared argument by argument, a guard breaks ties between otherwise equal handlers
+pub fn specificity(args: &[Node], guard: &Option<Box<Node>>) -> (Vec<(u8, usize)>, bool) {
+    (
+        args.iter()
+            .map(|arg| (pattern_rank(arg), pattern_detail(arg)))
+            .collect(),
+        guard.is_some(),
+    )
+}
+
+pub fn pattern_to_s(pattern: &Node) -> String {
+    fn props_to_s(props: &[(String, Node)]) -> String {
+        props
+            .iter()
+            .map(|(name, node)| match node {
+                Node::IdLookup(id) if id == name => format!("{};", name),
+                _ => format!("{}: {};", name, pattern_to_s(node)),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+    fn elements_to_s(nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| format!("{};", pattern_to_s(node)))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+    match pattern {
+        Node::Keyword(name) => format!(":{}", name),
+        Node::Int(val) => format!("{}", val),
+        Node::Str(val) => format!("\"{}\"", val),
+        Node::Operator(op) => op.to_owned(),
+        Node::IdLookup(name) => name.to_owned(),
+        Node::RecordConstructor(name, props) => format!("{}{{{}}}", name, props_to_s(props)),
+        Node::RecordLiteral(props) => format!("{{{}}}", props_to_s(props)),
+        Node::RecordUpdate(name, base, props) if props.is_empty() => {
+            format!("{}{{...{};}}", name, pattern_to_s(base))
+        }
+        Node::RecordUpdate(name, base, props) => format!(
+            "{}{{...{}; {}}}",
+            name,
+            pattern_to_s(base),
+            props_to_s(props)
+        ),
+        Node::VectorConstructor(name, nodes) => format!("{}[{}]", name, elements_to_s(nodes)),
+        Node::Dict(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, val)| format!("{} => {};", pattern_to_s(key), pattern_to_s(val)))
+                .collect();
+            format!("#{{{}}}", entries.join(" "))
+        }
+        Node::List(nodes) => format!("[{}]", elements_to_s(nodes)),
+        Node::Spread(node) => format!("...{}", pattern_to_s(node)),
+        Node::Unquote(node) => format!("^{}", pattern_to_s(node)),
+        Node::ParenExpr(node) => format!("({};)", pattern_to_s(node)),
+        Node::MethodCall(lhs, args) => {
+            let args: Vec<String> = args.iter().map(pattern_to_s).collect();
+            format!("{} {}", pattern_to_s(lhs), args.join(" "))
+        }
+        Node::Or(alternatives) => alternatives
+            .iter()
+            .map(pattern_to_s)
+            .collect::<Vec<String>>()
+            .join(" | "),
+        Node::As(name, node) => format!("{} @ {}", name, pattern_to_s(node)),
+        _ => "...".to_owned(),
+    }
+}
+
+fn handler_to_s(handler: &Handler) -> String {
+    def_to_s(handler.args, handler.guard)
+}
+
+pub fn def_to_s(args: &[Node], guard: &Option<Box<Node>>) -> String {
+    let args: Vec<String> = args.iter().map(pattern_to_s).collect();
+    match guard {
+        Some(guard) => format!("def {} when {}", args.join(" "), pattern_to_s(guard)),
+        None => format!("def {}", args.join(" ")),
+    }
+}
+
+// does `a` match every value `b` matches?
+fn subsumes(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::IdLookup(_), _) => true,
+        (Node::ParenExpr(a), b) | (Node::As(_, a), b) => subsumes(a, b),
+        (a, Node::ParenExpr(b)) | (a, Node::As(_, b)) => subsumes(a, b),
+        (a, Node::Or(bs)) => bs.iter().all(|b| subsumes(a, b)),
+        (Node::Or(alternatives), b) => alternatives.iter().any(|a| subsumes(a, b)),
+        (Node::Keyword(a), Node::Keyword(b)) => a == b,
+        (Node::Int(a), Node::Int(b)) => a == b,
+        (Node::Str(a), Node::Str(b)) => a == b,
+        (Node::Operator(a), Node::Operator(b)) => a == b,
+        (Node::Unquote(a), Node::Unquote(b)) => a == b,
+        (Node::RecordConstructor(a_name, a_props), Node::RecordConstructor(b_name, b_props)) => {
+            a_name == b_name && props_subsume(a_props, b_props)
+        }
+        (Node::RecordLiteral(a_props), Node::RecordConstructor(_, b_props))
+        | (Node::RecordLiteral(a_props), Node::RecordLiteral(b_props)) => {
+            props_subsume(a_props, b_props)
+        }
+        (Node::VectorConstructor(a_name, a), Node::VectorConstructor(b_name, b)) => {
+            a_name == b_name && elements_subsume(a, b)
+        }
+        (Node::List(a), Node::List(b)) => elements_subsume(a, b),
+        // every key `a` requires, `b` requires too
+        (Node::Dict(a_entries), Node::Dict(b_entries)) => a_entries.iter().all(|(key, a)| {
+            b_entries
+                .iter()
+                .any(|(b_key, b)| b_key == key && subsumes(a, b))
+        }),
+        _ => false,
+    }
+}
+
+fn props_subsume(a_props: &[(String, Node)], b_props: &[(String, Node)]) -> bool {
+    a_props.iter().all(|(name, a)| {
+        b_props
+            .iter()
+            .any(|(b_name, b)| b_name == name && subsumes(a, b))
+    })
+}
+
+fn split_around_spread(nodes: &[Node]) -> Option<(usize, usize)> {
+    nodes
+        .iter()
+        .position(|n| matches!(n, Node::Spread(_)))
+        .map(|idx| (idx, nodes.len() - idx - 1))
+}
+
+fn elements_subsume(a: &[Node], b: &[Node]) -> bool {
+    match (split_around_spread(a), split_around_spread(b)) {
+        (None, None) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| subsumes(a, b)),
+        // [x; ...rest;] subsumes [1; 2; 3;] & [x; ...rest;] subsumes [1; ...more;]
+        (Some((before, after)), b_spread) => {
+            let rest_is_wildcard = matches!(&a[before], Node::Spread(rest) if matches!(rest.as_ref(), Node::IdLookup(_)));
+            let long_enough = match b_spread {
+                Some((b_before, b_after)) => b_before >= before && b_after >= after,
+                None => b.len() >= before + after,
+            };
+            rest_is_wildcard
+                && long_enough
+                && a[..before]
+                    .iter()
+                    .zip(&b[..before])
+                    .all(|(a, b)| subsumes(a, b))
+                && a[before + 1..]
+                    .iter()
+                    .zip(&b[b.len() - after..])
+                    .all(|(a, b)| subsumes(a, b))
+        }
+        (None, Some(_)) => false,
+    }
+}
+
+// could some value match both `a` and `b`?
+fn overlaps(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::IdLookup(_), _) | (_, Node::IdLookup(_)) => true,
+        (Node::ParenExpr(a), b) | (Node::As(_, a), b) => overlaps(a, b),
+        (a, Node::ParenExpr(b)) | (a, Node::As(_, b)) => overlaps(a, b),
+        (Node::Or(alternatives), b) => alternatives.iter().any(|a| overlaps(a, b)),
+        (a, Node::Or(alternatives)) => alternatives.iter().any(|b| overlaps(a, b)),
+        // two different unquotes are assumed to be different values, like `^true` & `^false`
+        (Node::Unquote(a), Node::Unquote(b)) => a == b,
+        (Node::Unquote(_), _) | (_, Node::Unquote(_)) => true,
+        (Node::Keyword(a), Node::Keyword(b)) => a == b,
+        (Node::Int(a), Node::Int(b)) => a == b,
+        (Node::Str(a), Node::Str(b)) => a == b,
+        (Node::Operator(a), Node::Operator(b)) => a == b,
+        (Node::VectorConstructor(name, _), Node::Int(_))
+        | (Node::Int(_), Node::VectorConstructor(name, _)) => name == "Int",
+        (Node::VectorConstructor(name, _), Node::Str(_))
+        | (Node::Str(_), Node::VectorConstructor(name, _)) => name == "Str",
+        (Node::VectorConstructor(name, _), Node::Keyword(_))
+        | (Node::Keyword(_), Node::VectorConstructor(name, _)) => name == "Keyword",
+        (Node::RecordConstructor(a_name, a_props), Node::RecordConstructor(b_name, b_props)) => {
+            a_name == b_name && props_overlap(a_props, b_props)
+        }
+        (Node::RecordLiteral(a_props), Node::RecordConstructor(_, b_props))
+        | (Node::RecordConstructor(_, a_props), Node::RecordLiteral(b_props))
+        | (Node::RecordLiteral(a_props), Node::RecordLiteral(b_props)) => {
+            props_overlap(a_props, b_props)
+        }
+        (Node::VectorConstructor(a_name, a), Node::VectorConstructor(b_name, b)) => {
+            a_name == b_name && elements_overlap(a, b)
+        }
+        (Node::List(a), Node::List(b)) => elements_overlap(a, b),
+        (Node::Dict(a_entries), Node::Dict(b_entries)) => a_entries.iter().all(|(key, a)| {
+            b_entries
+                .iter()
+                .all(|(b_key, b)| b_key != key || overlaps(a, b))
+        }),
+        _ => false,
+    }
+}
+
+fn props_overlap(a_props: &[(String, Node)], b_props: &[(String, Node)]) -> bool {
+    a_props.iter().all(|(name, a)| {
+        b_props
+            .iter()
+            .all(|(b_name, b)| b_name != name || overlaps(a, b))
+    })
+}
+
+fn elements_overlap(a: &[Node], b: &[Node]) -> bool {
+    match (split_around_spread(a), split_around_spread(b)) {
+        (None, None) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| overlaps(a, b)),
+        // be conservative about lists of unknown length
+        _ => true,
+    }
+}
+
+// a name used twice in one handler pins the second use, so it isn't a wildcard
+fn has_repeated_names(args: &[Node]) -> bool {
+    fn collect<'a>(node: &'a Node, names: &mut HashMap<&'a str, usize>) {
+        match node {
+            Node::IdLookup(name) if name != "_" => *names.entry(name).or_default() += 1,
+            Node::As(name, node) => {
+                *names.entry(name).or_default() += 1;
+                collect(node, names);
+            }
+            Node::Spread(node) | Node::ParenExpr(node) => collect(node, names),
+            Node::List(nodes) | Node::VectorConstructor(_, nodes) | Node::Or(nodes) => {
+                nodes.iter().for_each(|node| collect(node, names))
+            }
+            Node::RecordConstructor(_, props) | Node::RecordLiteral(props) => {
+                props.iter().for_each(|(_, node)| collect(node, names))
+            }
+            Node::Dict(entries) => entries.iter().for_each(|(_, node)| collect(node, names)),
+            _ => (),
+        }
+    }
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    args.iter().for_each(|node| collect(node, &mut names));
+    names.values().any(|count| *count > 1)
+}
+
+fn check_handlers(handlers: &[Handler], dispatch: Dispatch, warnings: &mut Vec<Warning>) {
+    for (j, later) in handlers.iter().enumerate() {
+        for earlier in &handlers[..j] {
+            let earlier_spec = specificity(earlier.args, earlier.guard);
+            let later_spec = specificity(later.args, later.guard);
+            let shadows = earlier.guard.is_none()
+                && !has_repeated_names(earlier.args)
+                && elements_subsume(earlier.args, later.args)
+                && (dispatch == Dispatch::Declaration || later_spec <= earlier_spec);
+            if shadows {
+                warnings.push(Warning {
+                    position: later.position,
+                    message: format!(
+                        "`{}` is unreachable, `{}` matches everything it does",
+                        handler_to_s(later),
+                        handler_to_s(earlier)
+                    ),
+                });
+                break;
+            }
+            // in declaration order an overlap is resolved by the order itself
+            let ambiguous = dispatch == Dispatch::Specificity
+                && later.guard.is_none()
+                && earlier.guard.is_none()
+                && earlier_spec == later_spec
+                && elements_overlap(earlier.args, later.args)
+                && !elements_subsume(later.args, earlier.args);
+            if ambiguous {
+                warnings.push(Warning {
+                    position: later.position,
+                    message: format!(
+                        "`{}` overlaps with `{}`, neither is more specific",
+                        handler_to_s(later),
+                        handler_to_s(earlier)
+                    ),
+                });
+            }
+        }
+    }
+}
+
+fn handlers_of(defs: &[Node]) -> Vec<Handler<'_>> {
+    defs.iter()
+        .filter_map(|node| match node {
+            Node::Def(args, guard, _, position) => Some(Handler {
+                args,
+                guard,
+                position: *position,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+// object literals can appear anywhere, each one is its own class
+fn collect_objects<'a>(node: &'a Node, objects: &mut Vec<&'a Vec<Node>>) {
+    match node {
+        Node::Object(defs) => {
+            objects.push(defs);
+            defs.iter().for_each(|def| collect_objects(def, objects));
+        }
+        Node::Def(_, _, body, _) => collect_objects(body, objects),
+        Node::Class(_, _, _, defs) | Node::List(defs) | Node::VectorConstructor(_, defs) => {
+            defs.iter().for_each(|node| collect_objects(node, objects))
+        }
+        Node::MethodCall(lhs, args) => {
+            collect_objects(lhs, objects);
+            args.iter().for_each(|node| collect_objects(node, objects));
+        }
+        Node::RecordConstructor(_, props) | Node::RecordLiteral(props) => props
+            .iter()
+            .for_each(|(_, node)| collect_objects(node, objects)),
+        Node::RecordUpdate(_, base, props) => {
+            collect_objects(base, objects);
+            props
+                .iter()
+                .for_each(|(_, node)| collect_objects(node, objects));
+        }
+        Node::Dict(entries) => entries.iter().for_each(|(key, val)| {
+            collect_objects(key, objects);
+            collect_objects(val, objects);
+        }),
+        Node::Assign(_, node)
+        | Node::Unquote(node)
+        | Node::ParenExpr(node)
+        | Node::Spread(node) => collect_objects(node, objects),
+        _ => (),
+    }
+}
+
+struct Family<'a> {
+    name: &'a str,
+    members: &'a [String],
+}
+
+// which members of `family` does `pattern` match, or None if it doesn't dispatch on the family
+fn members_matched(
+    pattern: &Node,
+    family: &Family,
+    constants: &HashMap<&str, &str>,
+) -> Option<Vec<String>> {
+    let class_pattern = |name: &String, covers: bool| {
+        if name == family.name {
+            Some(if covers {
+                family.members.to_vec()
+            } else {
+                vec![]
+            })
+        } else if family.members.contains(name) {
+            Some(if covers {
+                vec![name.to_owned()]
+            } else {
+                vec![]
+            })
+        } else {
+            None
+        }
+    };
+    match pattern {
+        // fields bound to names are assumed to be there
+        Node::RecordConstructor(name, props) => class_pattern(
+            name,
+            props
+                .iter()
+                .all(|(_, node)| matches!(node, Node::IdLookup(_))),
+        ),
+        Node::VectorConstructor(name, nodes) => class_pattern(
+            name,
+            nodes.iter().all(|node| matches!(node, Node::IdLookup(_))),
+        ),
+        // ^true where `true := TrueClass{};`
+        Node::Unquote(node) => match node.as_ref() {
+            Node::IdLookup(name) => constants
+                .get(name.as_str())
+                .and_then(|class| class_pattern(&class.to_string(), true)),
+            _ => None,
+        },
+        Node::As(_, node) | Node::ParenExpr(node) => members_matched(node, family, constants),
+        Node::Or(alternatives) => alternatives
+            .iter()
+            .filter_map(|alternative| members_matched(alternative, family, constants))
+            .reduce(|mut members, more| {
+                members.extend(more);
+                members
+            }),
+        _ => None,
+    }
+}
+
+// patterns that only differ in the names they bind are the same
+fn pattern_key(pattern: &Node) -> String {
+    match pattern {
+        Node::IdLookup(_) => "_".to_owned(),
+        Node::As(_, node) => pattern_key(node),
+        _ => pattern_to_s(pattern),
+    }
+}
+
+// handlers that dispatch on a sealed family at one argument, and agree on every other
+// argument, should handle every member of the family
+fn check_exhaustive(
+    handlers: &[Handler],
+    families: &[Family],
+    constants: &HashMap<&str, &str>,
+    warnings: &mut Vec<Warning>,
+) {
+    for family in families {
+        let mut groups: Vec<(usize, Vec<String>, Vec<&Handler>)> = vec![];
+        for handler in handlers {
+            for (idx, arg) in handler.args.iter().enumerate() {
+                if members_matched(arg, family, constants).is_none() {
+                    continue;
+                }
+                let others: Vec<String> = handler
+                    .args
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_idx, _)| *other_idx != idx)
+                    .map(|(_, arg)| pattern_key(arg))
+                    .collect();
+                match groups.iter_mut().find(|(group_idx, group_others, _)| {
+                    *group_idx == idx && *group_others == others
+                }) {
+                    Some((_, _, group)) => group.push(handler),
+                    None => groups.push((idx, others, vec![handler])),
+                }
+            }
+        }
+
+        for (idx, others, group) in groups {
+            let catch_all = handlers.iter().any(|handler| {
+                handler.guard.is_none()
+                    && handler.args.len() == others.len() + 1
+                    && matches!(handler.args[idx], Node::IdLookup(_))
+                    && handler
+                        .args
+                        .iter()
+                        .enumerate()
+                        .filter(|(other_idx, _)| *other_idx != idx)
+                        .map(|(_, arg)| pattern_key(arg))
+                        .eq(others.iter().cloned())
+            });
+            if catch_all {
+                continue;
+            }
+            let covered: Vec<String> = group
+                .iter()
+                .filter(|handler| handler.guard.is_none())
+                .filter_map(|handler| members_matched(&handler.args[idx], family, constants))
+                .flatten()
+                .collect();
+            let missing: Vec<&str> = family
+                .members
+                .iter()
+                .filter(|member| !covered.contains(member))
+                .map(|member| member.as_str())
+                .collect();
+            if !missing.is_empty() {
+                let handlers: Vec<String> = group
+                    .iter()
+                    .map(|handler| format!("`{}`", handler_to_s(handler)))
+                    .collect();
+                warnings.push(Warning {
+                    position: group[0].position,
+                    message: format!(
+                        "{} {} handle {} from {}",
+                        handlers.join(", "),
+                        if handlers.len() == 1 {
+                            "doesn't"
+                        } else {
+                            "don't"
+                        },
+                        missing.join(", "),
+                        family.name
+                    ),
+                });
+            }
+        }
+    }
+}
+
+// reports handlers that can never be picked, handlers that are ambiguous, and
+// handlers that miss a member of a sealed family
+pub fn check(ast: &[Node], dispatch: Dispatch) -> Vec<Warning> {
+    let mut warnings: Vec<Warning> = vec![];
+
+    // a class can be reopened, so its handlers are gathered across the program
+    let mut class_names: Vec<&str> = vec![];
+    let mut classes: HashMap<&str, Vec<Node>> = HashMap::new();
+    let mut top_level: Vec<Node> = vec![];
+    let mut families: Vec<Family> = vec![];
+    let mut constants: HashMap<&str, &str> = HashMap::new();
+    for node in ast {
+        match node {
+            Node::Class(name, members, _, defs) => {
+                if !classes.contains_key(name.as_str()) {
+                    class_names.push(name);
+                }
+                classes
+                    .entry(name)
+                    .or_default()
+                    .extend(defs.iter().cloned());
+                // declaring a family again fails when the program runs
+                if !members.is_empty() && !families.iter().any(|family| family.name == name) {
+                    families.push(Family { name, members });
+                }
+            }
+            Node::Def(_, _, _, _) => top_level.push(node.clone()),
+            Node::Assign(name, value) => match value.as_ref() {
+                Node::RecordConstructor(class, props) if props.is_empty() => {
+                    constants.insert(name, class);
+                }
+                Node::VectorConstructor(class, nodes) if nodes.is_empty() => {
+                    constants.insert(name, class);
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    let mut handler_lists: Vec<Vec<Handler>> = vec![handlers_of(&top_level)];
+    for name in class_names {
+        handler_lists.push(handlers_of(&classes[name]));
+    }
+    let mut objects: Vec<&Vec<Node>> = vec![];
+    ast.iter()
+        .for_each(|node| collect_objects(node, &mut objects));
+    for defs in objects {
+        handler_lists.push(handlers_of(defs));
+    }
+
+    for handlers in handler_lists {
+        check_handlers(&handlers, dispatch, &mut warnings);
+        check_exhaustive(&handlers, &families, &constants, &mut warnings);
+    }
+
+    warnings.sort_by_key(|warning| warning.position);
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::Parser;
+
+    // the messages of the warnings for `program`, run after the prelude
+    fn warnings(program: &str, dispatch: Dispatch) -> Vec<String> {
+        let tokens = tokenize(include_str!("prelude.pnt").to_owned() + program);
+        let ast = Parser { tokens, idx: 0 }.parse();
+        check(&ast, dispatch)
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    #[test]
+    fn a_handler_after_a_wildcard_is_unreachable() {
+        let program = "
+            class Two
+              def _ -> false;
+              def 2 -> true;
+            end
+        ";
+        assert_eq!(
+            warnings(program, Dispatch::Declaration),
+            vec!["`def 2` is unreachable, `def _` matches everything it does"]
+        );
+        // a more specific handler is still picked when dispatching by specificity
+        assert!(warnings(program, Dispatch::Specificity).is_empty());
+    }
+
+    #[test]
+    fn guards_and_repeated_names_dont_shadow() {
+        let program = "
+            class Guarded
+              def n when n > 1 -> 1;
+              def :same? a a -> true;
+              def 2 -> 2;
+              def :same? 1 2 -> false;
+            end
+        ";
+        assert!(warnings(program, Dispatch::Declaration).is_empty());
+    }
+
+    #[test]
+    fn overlapping_handlers_of_equal_specificity_are_ambiguous() {
+        let program = "
+            class Pairs
+              def [1; x;] -> :first;
+              def [x; 2;] -> :second;
+            end
+        ";
+        assert_eq!(
+            warnings(program, Dispatch::Specificity),
+            vec!["`def [x; 2;]` overlaps with `def [1; x;]`, neither is more specific"]
+        );
+        assert!(warnings(program, Dispatch::Declaration).is_empty());
+    }
+
+    #[test]
+    fn a_rest_pattern_subsumes_longer_lists() {
+        let program = "
+            class Lists
+              def [x; ...rest;] -> 1;
+              def [1; 2; 3;] -> 2;
+              def [] -> 3;
+            end
+        ";
+        assert_eq!(
+            warnings(program, Dispatch::Declaration),
+            vec!["`def [1; 2; 3;]` is unreachable, `def [x; ...rest;]` matches everything it does"]
+        );
+    }
+
+    #[test]
+    fn a_missing_family_member_is_reported() {
+        let program = "
+            class Shape = Circle | Square | Triangle;
+            class Area
+              def :of Circle{r;} -> r * r. * 3;
+              def :of Square{side;} -> side * side;
+            end
+        ";
+        assert_eq!(
+            warnings(program, Dispatch::Declaration),
+            vec!["`def :of Circle{r;}`, `def :of Square{side;}` don't handle Triangle from Shape"]
+        );
+    }
+
+    #[test]
+    fn covering_every_member_or_a_wildcard_is_exhaustive() {
+        let program = "
+            class Shape = Circle | Square;
+            class Area
+              def :of Circle{r;} -> r;
+              def :of Square{side;} -> side;
+              def :name Circle{} -> :circle;
+              def :name _ -> :shape;
+            end
+        ";
+        assert!(warnings(program, Dispatch::Declaration).is_empty());
+    }
+
+    #[test]
+    fn pinned_constants_count_as_their_class() {
+        let program = "
+            class Not
+              def ^true -> false;
+            end
+        ";
+        assert_eq!(
+            warnings(program, Dispatch::Declaration),
+            vec!["`def ^true` doesn't handle FalseClass from Bool"]
+        );
+        let program = "
+            class Not
+              def ^true -> false;
+              def ^false -> true;
+            end
+        ";
+        assert!(warnings(program, Dispatch::Declaration).is_empty());
+    }
+}
@@ -1,8 +1,15 @@
 use core::panic;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 
+use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
+use crate::analyzer::{def_to_s, pattern_to_s, specificity};
 use crate::parser::Node;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,24 +17,113 @@ pub enum Object {
     Nil,
     Keyword(String),
     Str(String),
-    Int(usize),
+    Int(i64),
     Instance(Uuid, Vec<(String, Object)>),
     Class(Uuid),
     Operator(String),
     List(Vec<Object>),
+    Cell(Ref),
+    // start, end & whether the end is included, 1..=3 is Range(1, 3, true)
+    Range(i64, i64, bool),
+    Iter(Box<Lazy>),
+    // unique per `Sym[...]`, the keyword is only a description
+    Sym(Uuid, Option<String>),
+    // `_ + 1. * 2` is the messages [[+, 1], [*, 2]], replayed on whatever it's sent next
+    Placeholder(Vec<Vec<Object>>),
+    Dict(Dict),
+    // a dict whose values are all nil
+    Set(Dict),
+}
+
+// keys can be any value, `index` finds a key's entries by its `:hash`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Dict {
+    entries: Vec<(Object, Object)>,
+    index: HashMap<i64, Vec<usize>>,
+}
+
+// a sequence that is only worked out as `:next` pulls from it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lazy {
+    Items(Rc<Vec<Object>>, usize),
+    Ints(i64, i64, bool),
+    Map(Object, Object),
+    Filter(Object, Object),
+    Take(Object, usize),
+}
+
+// a shared, mutable slot, two refs are only equal if they are the same slot
+#[derive(Clone)]
+pub struct Ref(Rc<RefCell<Object>>);
+
+impl Ref {
+    fn new(val: Object) -> Ref {
+        Ref(Rc::new(RefCell::new(val)))
+    }
+
+    fn get(&self) -> Object {
+        self.0.borrow().clone()
+    }
+
+    fn set(&self, val: Object) {
+        *self.0.borrow_mut() = val;
+    }
+}
+
+impl PartialEq for Ref {
+    fn eq(&self, other: &Ref) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Hash for Ref {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).hash(state)
+    }
+}
+
+// the contents can refer back to the cell
+impl std::fmt::Debug for Ref {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ref({:p})", Rc::as_ptr(&self.0))
+    }
 }
 
 impl Object {
-    pub fn to_s(&self, class_env: &HashMap<Uuid, Class>) -> String {
+    // the objects an instance or a list is made of
+    fn elements(&self) -> Vec<Object> {
+        match self {
+            Object::Instance(_, props) => props.iter().map(|(_, val)| val.clone()).collect(),
+            Object::List(items) => items.clone(),
+            Object::Cell(cell) => vec![cell.get()],
+            Object::Dict(dict) => dict
+                .entries
+                .iter()
+                .flat_map(|(key, val)| [key.clone(), val.clone()])
+                .collect(),
+            Object::Set(set) => set.entries.iter().map(|(item, _)| item.clone()).collect(),
+            _ => vec![],
+        }
+    }
+
+    // `elements` are this object's elements, already formatted
+    fn format(&self, class_env: &HashMap<Uuid, Class>, elements: &[String]) -> String {
         match self {
             Object::Instance(class_id, props) => {
                 let class = class_env.get(class_id).unwrap();
                 if ["Int", "String"].contains(&class.name.as_str()) {
-                    props.get(0).unwrap().1.to_s(class_env)
+                    elements.first().unwrap().to_owned()
                 } else if class.name == "TrueClass" {
                     "true".to_string()
                 } else if class.name == "FalseClass" {
                     "false".to_string()
+                } else if class.fields.is_none()
+                    // `V[]` & `V{}` are the same instance, a named class shows it as `V[]`
+                    && (!props.is_empty() || !["Object", "<anon class>"].contains(&class.name.as_str()))
+                    && positional_values(class_id, props, class_env).is_some()
+                {
+                    let items: Vec<String> = elements.iter().map(|e| format!("{};", e)).collect();
+                    format!("{}[{}]", class.name, items.join(" "))
                 } else {
                     let name = if class.name == "Object" {
                         "".to_owned()
@@ -36,7 +132,12 @@ impl Object {
                     };
                     let props = props
                         .iter()
-                        .map(|(name, val)| format!("{}: {}", name, val.to_s(class_env)))
+                        .zip(elements)
+                        .map(|((name, _), val)| match key_sym(name) {
+                            // {^step: 1;} prints as {Sym[:step;]: 1;}
+                            Some(sym) => format!("{}: {}", sym.to_s(class_env), val),
+                            None => format!("{}: {}", name, val),
+                        })
                         .reduce(|str, cur| format!("{}; {}", str, cur))
                         .map(|s| s + ";")
                         .unwrap_or("".to_owned());
@@ -49,37 +150,102 @@ impl Object {
             Object::Int(val) => format!("{}", val),
             Object::Class(uuid) => format!("[{}]", class_env.get(uuid).unwrap().name),
             Object::Operator(op) => format!("`{}`", op),
-            Object::List(items) => {
-                format!(
-                    "[{};]",
-                    items
-                        .iter()
-                        .map(|item| item.to_s(class_env))
-                        .reduce(|a, b| format!("{}; {}", a, b))
-                        .unwrap()
-                )
+            Object::List(_) => elements
+                .iter()
+                .cloned()
+                .reduce(|a, b| format!("{}; {}", a, b))
+                .map(|items| format!("[{};]", items))
+                .unwrap_or("[]".to_owned()),
+            Object::Cell(_) => format!("Cell[{};]", elements.first().unwrap()),
+            Object::Range(start, end, true) => format!("{}..={}", start, end),
+            Object::Range(start, end, false) => format!("{}..{}", start, end),
+            Object::Iter(_) => "Iter[...]".to_owned(),
+            Object::Sym(_, Some(desc)) => format!("Sym[:{};]", desc),
+            Object::Sym(_, None) => "Sym[]".to_owned(),
+            Object::Dict(_) => {
+                let entries: Vec<String> = elements
+                    .chunks(2)
+                    .map(|entry| format!("{} => {};", entry[0], entry[1]))
+                    .collect();
+                format!("#{{{}}}", entries.join(" "))
+            }
+            Object::Set(_) => {
+                let items: Vec<String> = elements.iter().map(|e| format!("{};", e)).collect();
+                format!("Set[{}]", items.join(" "))
+            }
+            Object::Placeholder(messages) => messages
+                .iter()
+                .map(|args| {
+                    args.iter()
+                        .map(|arg| match arg {
+                            // `_ + 1` reads like the code that made it
+                            Object::Operator(op) => op.to_owned(),
+                            _ => arg.to_s(class_env),
+                        })
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                })
+                .fold("_".to_owned(), |str, message| match str.as_str() {
+                    "_" => format!("_ {}", message),
+                    _ => format!("{}. {}", str, message),
+                }),
+        }
+    }
+
+    // the raw structure, ignoring any `def :to_s`
+    pub fn to_s(&self, class_env: &HashMap<Uuid, Class>) -> String {
+        self.to_s_seen(class_env, &mut vec![])
+    }
+
+    // `seen` are the cells being printed, a cell inside itself prints as `Cell[...]`
+    fn to_s_seen(&self, class_env: &HashMap<Uuid, Class>, seen: &mut Vec<Ref>) -> String {
+        if let Object::Cell(cell) = self {
+            if seen.contains(cell) {
+                return "Cell[...]".to_owned();
             }
+            seen.push(cell.clone());
+        }
+        let elements: Vec<String> = self
+            .elements()
+            .iter()
+            .map(|element| element.to_s_seen(class_env, seen))
+            .collect();
+        if let Object::Cell(_) = self {
+            seen.pop();
         }
+        self.format(class_env, &elements)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dispatch {
+    // the first handler that matches, in the order they were defined
+    Declaration,
+    // the most specific handler that matches, literal over class pattern over wildcard
+    Specificity,
+}
+
 #[derive(Debug, Clone)]
 pub struct Class {
     name: String,
-    methods: Vec<Node>,
+    // shared, so looking up a handler doesn't copy the class
+    methods: Vec<Rc<Node>>,
     superclass: Option<Uuid>,
+    dispatch: Dispatch,
+    // class Point{x; y;}, classes without a declaration take any fields
+    fields: Option<Vec<String>>,
 }
 
 fn match_record_properties_pattern(
     pattern_props: &Vec<(String, Node)>,
-    obj_props: &Vec<(String, Object)>,
+    obj_props: &[(String, Object)],
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
     local_env: &mut HashMap<String, Object>,
 ) -> bool {
-    let obj_map: HashMap<String, Object> = obj_props.iter().map(|t| t.clone()).collect();
+    let obj_map: HashMap<String, Object> = obj_props.iter().cloned().collect();
     for (key, node) in pattern_props {
-        match obj_map.get(key) {
+        match obj_map.get(&prop_key(key, env)) {
             Some(val) => {
                 if !match_pattern(node, val, env, class_env, local_env) {
                     return false;
@@ -101,21 +267,23 @@ fn match_pattern(
     match (a, b) {
         (Node::Keyword(a), Object::Keyword(b)) => a == b,
         (Node::Keyword(_), _) => false,
-        (Node::Class(_, _), _) => todo!("class eq"),
+        (Node::Class(_, _, _, _), _) => todo!("class eq"),
         (Node::MethodCall(_, _), _) => todo!("not sure"),
         (Node::RecordConstructor(a, pattern_props), Object::Instance(id, obj_props)) => {
-            if !class_env.get(id).map(|c| &c.name == a).unwrap_or(false) {
+            if !is_instance_of(id, a, class_env) {
                 return false;
             }
             match_record_properties_pattern(pattern_props, obj_props, env, class_env, local_env)
         }
         (Node::RecordConstructor(_, _), _) => false,
-        (Node::Int(a), Object::Int(b)) => a == b,
+        (Node::RecordUpdate(_, _, _), _) => panic!("`...` can't be used in a record pattern"),
+        (Node::Int(a), Object::Int(b)) => *a as i64 == *b,
         (Node::Int(_), _) => false,
         (Node::IdLookup(name), _) if name == "self" => panic!("self is not a valid pattern"),
+        (Node::IdLookup(name), _) if name == "_" => true,
         (Node::IdLookup(name), obj) => {
-            if let Some(val) = local_env.get(name) {
-                val == obj
+            if let Some(val) = local_env.get(name).cloned() {
+                objects_equal(&val, obj, env, class_env)
             } else {
                 local_env.insert(name.to_string(), obj.clone());
                 true
@@ -126,32 +294,63 @@ fn match_pattern(
         (Node::Operator(_), _) => false,
         (Node::List(a), Object::List(b)) => match_vec(a, b, env, class_env, local_env),
         (Node::List(_), _) => false,
-        (Node::Def(_, _), _) => todo!(),
+        (Node::Def(_, _, _, _), _) => todo!(),
         (Node::Str(a), Object::Str(b)) => a == b,
         (Node::Str(_), _) => false,
         (Node::VectorConstructor(name, values), Object::Instance(id, props)) => {
-            assert!(values.len() == 1 && props.len() == 1);
-            if !class_env.get(id).map(|c| &c.name == name).unwrap_or(false) {
+            if !is_instance_of(id, name, class_env) {
                 return false;
             }
-
-            let lhs = values.first().unwrap();
-            let (name, val) = props.first().unwrap();
-            name == "value" && match_pattern(lhs, val, env, class_env, local_env)
-        }
-        (Node::VectorConstructor(name, values), Object::Keyword(_)) => {
-            values.len() == 1 && name == "Keyword"
+            match positional_values(id, props, class_env) {
+                Some(objs) => match_vec(values, &objs, env, class_env, local_env),
+                None => false,
+            }
         }
-        (Node::VectorConstructor(name, values), Object::Str(_)) => {
-            values.len() == 1 && name == "Str"
+        // Int[n] matches the int itself
+        (
+            Node::VectorConstructor(name, values),
+            obj @ (Object::Keyword(_) | Object::Str(_) | Object::Int(_)),
+        ) => {
+            let class_name = match obj {
+                Object::Keyword(_) => "Keyword",
+                Object::Str(_) => "Str",
+                _ => "Int",
+            };
+            name == class_name
+                && match_vec(values, std::slice::from_ref(obj), env, class_env, local_env)
         }
-        (Node::VectorConstructor(name, values), Object::Int(_)) => {
-            values.len() == 1 && name == "Int"
+        (Node::VectorConstructor(name, values), Object::Cell(cell)) => {
+            name == "Cell" && match_vec(values, &[cell.get()], env, class_env, local_env)
         }
         (Node::VectorConstructor(_, _), _) => false,
-        (Node::Unquote(node), rhs) => eval_node(node, env, class_env) == *rhs,
+        (Node::Unquote(node), rhs) => {
+            // names bound earlier in the pattern can be pinned with `^name`
+            let mut scope = env.clone();
+            scope.extend(local_env.clone());
+            let val = eval_node(node, &mut scope, class_env);
+            objects_equal(&val, rhs, env, class_env)
+        }
         (Node::ParenExpr(node), rhs) => match_pattern(node, rhs, env, class_env, local_env),
-        (Node::Spread(_), _) => todo!("AH"),
+        (Node::Or(alternatives), rhs) => alternatives.iter().any(|alternative| {
+            let mut alternative_env = local_env.clone();
+            let matched = match_pattern(alternative, rhs, env, class_env, &mut alternative_env);
+            if matched {
+                *local_env = alternative_env;
+            }
+            matched
+        }),
+        (Node::As(name, pattern), rhs) => {
+            match_pattern(pattern, rhs, env, class_env, local_env)
+                && match_pattern(
+                    &Node::IdLookup(name.to_owned()),
+                    rhs,
+                    env,
+                    class_env,
+                    local_env,
+                )
+        }
+        // only ever given the rest of a list, by match_vec
+        (Node::Spread(node), rhs) => match_pattern(node, rhs, env, class_env, local_env),
         (Node::Object(_), _) => todo!(),
         (Node::RecordLiteral(pattern_props), Object::Instance(_, obj_props)) => {
             match_record_properties_pattern(pattern_props, obj_props, env, class_env, local_env)
@@ -161,98 +360,267 @@ fn match_pattern(
         (Node::RecordLiteral(_), Object::Str(_)) => todo!(),
         (Node::RecordLiteral(_), Object::Int(_)) => todo!(),
         (Node::RecordLiteral(_), _) => false,
+        // #{:a => x;} matches dicts with at least an `:a` key
+        (Node::Dict(entries), Object::Dict(dict)) => entries.iter().all(|(key, pattern)| {
+            let mut scope = env.clone();
+            scope.extend(local_env.clone());
+            let key = eval_node(key, &mut scope, class_env);
+            match dict_find(dict, &key, env, class_env) {
+                Some(idx) => {
+                    match_pattern(pattern, &dict.entries[idx].1, env, class_env, local_env)
+                }
+                None => false,
+            }
+        }),
+        (Node::Dict(_), _) => false,
     }
 }
 
 fn match_arg_list(
-    method_args: &Vec<Node>,
+    method_args: &[Node],
     args: &[Object],
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
 ) -> bool {
-    if method_args
-        .iter()
-        .find(|n| matches!(n, Node::Spread(_)))
-        .is_some()
-    {
-        // 1: check if there are enough args
-        if args.len() < method_args.len() - 1 {
-            return false;
-        }
-
-        // 2: get method_args before spread
-        let before_spread: Vec<Node> = method_args
-            .iter()
-            .take_while(|n| !matches!(n, Node::Spread(_)))
-            .map(|n| n.clone())
-            .collect();
-        // 3: get method_args after spread
-        let after_spread: Vec<Node> = method_args
-            .iter()
-            .rev()
-            .take_while(|n| !matches!(n, Node::Spread(_)))
-            .collect::<Vec<&Node>>()
-            .iter()
-            .rev()
-            .map(|n| (*n).clone())
-            .collect();
+    match_vec(method_args, args, env, class_env, &mut HashMap::new())
+}
 
-        let mut local_env: HashMap<String, Object> = HashMap::new();
+// returns the number of elements the patterns before & after the spread need
+fn split_around_spread(patterns: &[Node]) -> Option<(usize, usize)> {
+    assert!(at_most_one_spread_arg(patterns));
+    patterns
+        .iter()
+        .position(|n| matches!(n, Node::Spread(_)))
+        .map(|idx| (idx, patterns.len() - idx - 1))
+}
 
-        // 4: get actual args before spread
+fn match_vec(
+    method_args: &[Node],
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+    local_env: &mut HashMap<String, Object>,
+) -> bool {
+    match split_around_spread(method_args) {
+        // [head; ...tail;]
+        Some((before, after)) => {
+            if args.len() < before + after {
+                return false;
+            }
+            let rest_end = args.len() - after;
+            match_vec(
+                &method_args[..before],
+                &args[..before],
+                env,
+                class_env,
+                local_env,
+            ) && match_pattern(
+                &method_args[before],
+                &Object::List(args[before..rest_end].to_vec()),
+                env,
+                class_env,
+                local_env,
+            ) && match_vec(
+                &method_args[before + 1..],
+                &args[rest_end..],
+                env,
+                class_env,
+                local_env,
+            )
+        }
+        None => {
+            method_args.len() == args.len()
+                && method_args
+                    .iter()
+                    .zip(args)
+                    .all(|(a, b)| match_pattern(a, b, env, class_env, local_env))
+        }
+    }
+}
 
-        let args_before_spread: Vec<Object> = args
-            .iter()
-            .take(before_spread.len())
-            .map(|n| n.clone())
-            .collect();
+fn bool_object(val: bool, env: &HashMap<String, Object>) -> Object {
+    let name = if val { "true" } else { "false" };
+    match env.get(name) {
+        Some(obj) => obj.clone(),
+        None => panic!("`{}` is not defined", name),
+    }
+}
 
-        // 5: how many args are there?
-        let num_spread_args = args.len() - (before_spread.len() + after_spread.len());
+// everything except `false` & `nil` is truthy
+fn is_truthy(obj: &Object, env: &HashMap<String, Object>) -> bool {
+    match (obj, env.get("FalseClass")) {
+        (Object::Nil, _) => false,
+        (Object::Instance(class_id, _), Some(Object::Class(false_class_id))) => {
+            class_id != false_class_id
+        }
+        _ => true,
+    }
+}
 
-        match_vec(
-            &before_spread,
-            &args_before_spread,
-            env,
-            class_env,
-            &mut local_env,
-        ) && match_vec(
-            &after_spread,
-            &args
-                .iter()
-                .skip(before_spread.len() + num_spread_args)
-                .map(|n| n.clone())
-                .collect::<Vec<Object>>(),
-            env,
-            class_env,
-            &mut local_env,
-        )
+// `a == b`, using the receiver's `def ==` if it has one
+fn objects_equal(
+    a: &Object,
+    b: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> bool {
+    let args = [Object::Operator("==".to_owned()), b.clone()];
+    if defines_selector(a, &args, env, class_env) {
+        is_truthy(&send_message(a, &args, env, class_env), env)
     } else {
-        match_vec(method_args, args, env, class_env, &mut HashMap::new())
+        structurally_equal(a, b, env, class_env)
     }
 }
 
-fn match_vec(
-    method_args: &Vec<Node>,
-    args: &[Object],
+// same class & same fields, each compared with `objects_equal`
+fn structurally_equal(
+    a: &Object,
+    b: &Object,
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
-    local_env: &mut HashMap<String, Object>,
 ) -> bool {
-    if method_args.len() == 1 && matches!(method_args.first().unwrap(), Node::Spread(_)) {
-        if let Node::Spread(node) = method_args.first().unwrap() {
-            assert!(matches!(node.as_ref(), Node::IdLookup(_)));
-            true
-        } else {
-            panic!("um");
+    match (a, b) {
+        (Object::Instance(a_id, a_props), Object::Instance(b_id, b_props)) => {
+            a_id == b_id
+                && a_props.len() == b_props.len()
+                && a_props
+                    .iter()
+                    .zip(b_props)
+                    .all(|((a_name, a_val), (b_name, b_val))| {
+                        a_name == b_name && objects_equal(a_val, b_val, env, class_env)
+                    })
+        }
+        (Object::Dict(a_dict), Object::Dict(b_dict))
+        | (Object::Set(a_dict), Object::Set(b_dict)) => {
+            a_dict.entries.len() == b_dict.entries.len()
+                && a_dict.entries.iter().all(|(key, a_val)| {
+                    match dict_find(b_dict, key, env, class_env) {
+                        Some(idx) => {
+                            objects_equal(a_val, &b_dict.entries[idx].1.clone(), env, class_env)
+                        }
+                        None => false,
+                    }
+                })
+        }
+        (Object::List(a_items), Object::List(b_items)) => {
+            a_items.len() == b_items.len()
+                && a_items
+                    .iter()
+                    .zip(b_items)
+                    .all(|(a_item, b_item)| objects_equal(a_item, b_item, env, class_env))
+        }
+        _ => a == b,
+    }
+}
+
+// objects that are `==` must hash the same, so `def ==` usually comes with `def :hash`
+fn hash_object(
+    obj: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> i64 {
+    let args = [Object::Keyword("hash".to_owned())];
+    if defines_selector(obj, &args, env, class_env) {
+        match send_message(obj, &args, env, class_env) {
+            Object::Int(hash) => hash,
+            other => panic!("`:hash` returned {}, not an Int", other.to_s(class_env)),
         }
     } else {
-        method_args.len() == args.len()
-            && method_args
+        structural_hash(obj, env, class_env)
+    }
+}
+
+fn structural_hash(
+    obj: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    std::mem::discriminant(obj).hash(&mut hasher);
+    match obj {
+        Object::Instance(class_id, props) => {
+            class_id.hash(&mut hasher);
+            for (name, val) in props {
+                name.hash(&mut hasher);
+                hash_object(val, env, class_env).hash(&mut hasher);
+            }
+        }
+        Object::List(items) => {
+            for item in items {
+                hash_object(item, env, class_env).hash(&mut hasher);
+            }
+        }
+        Object::Nil => {}
+        Object::Keyword(name) | Object::Str(name) | Object::Operator(name) => {
+            name.hash(&mut hasher)
+        }
+        Object::Int(val) => val.hash(&mut hasher),
+        Object::Class(class_id) => class_id.hash(&mut hasher),
+        Object::Cell(cell) => cell.hash(&mut hasher),
+        Object::Range(start, end, inclusive) => (start, end, inclusive).hash(&mut hasher),
+        Object::Iter(lazy) => format!("{:?}", lazy).hash(&mut hasher),
+        Object::Sym(id, _) => id.hash(&mut hasher),
+        Object::Placeholder(messages) => {
+            for arg in messages.iter().flatten() {
+                hash_object(arg, env, class_env).hash(&mut hasher);
+            }
+        }
+        // the same entries in any order hash the same
+        Object::Dict(dict) => dict
+            .entries
+            .iter()
+            .fold(0i64, |sum, (key, val)| {
+                let entry = hash_object(key, env, class_env)
+                    ^ hash_object(val, env, class_env).rotate_left(1);
+                sum.wrapping_add(entry)
+            })
+            .hash(&mut hasher),
+        Object::Set(set) => {
+            "Set".hash(&mut hasher);
+            set.entries
                 .iter()
-                .zip(args)
-                .all(|(a, b)| match_pattern(a, b, env, class_env, local_env))
+                .fold(0i64, |sum, (item, _)| {
+                    sum.wrapping_add(hash_object(item, env, class_env))
+                })
+                .hash(&mut hasher)
+        }
     }
+    hasher.finish() as i64
+}
+
+// -1 is the last index, `None` when it's out of range
+fn index_of(idx: i64, len: usize) -> Option<usize> {
+    let idx = if idx < 0 {
+        len.checked_sub(idx.unsigned_abs() as usize)?
+    } else {
+        usize::try_from(idx).ok()?
+    };
+    (idx < len).then_some(idx)
+}
+
+// a stable merge sort that only asks `before`, so a comparator that isn't a
+// consistent order still gives back every item, in some order
+fn merge_sort(
+    mut items: Vec<Object>,
+    before: &mut impl FnMut(&Object, &Object) -> bool,
+) -> Vec<Object> {
+    if items.len() <= 1 {
+        return items;
+    }
+    let right = merge_sort(items.split_off(items.len() / 2), before);
+    let left = merge_sort(items, before);
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        let next = if before(b, a) {
+            right.next()
+        } else {
+            left.next()
+        };
+        merged.extend(next);
+    }
+    merged.extend(left.chain(right));
+    merged
 }
 
 fn try_eval_native_list_fn(
@@ -261,183 +629,1249 @@ fn try_eval_native_list_fn(
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
 ) -> Option<Object> {
+    // taking or dropping a negative count is the same as 0
+    let count = |n: &i64| usize::try_from(*n).unwrap_or(0);
     match args {
-        [Object::Keyword(name)] if name == "log" => {
-            println!("{}", Object::List(items.to_vec()).to_s(class_env));
-            Some(Object::Nil)
+        [Object::Int(val)] => Some(
+            index_of(*val, items.len())
+                .and_then(|idx| items.get(idx))
+                .cloned()
+                .unwrap_or(Object::Nil),
+        ),
+        [Object::Keyword(name)] if name == "len" => Some(Object::Int(items.len() as i64)),
+        [Object::Keyword(name)] if name == "first" => {
+            Some(items.first().cloned().unwrap_or(Object::Nil))
         }
-        [Object::Int(val)] => Some(items.get(*val).map(|n| n.clone()).unwrap_or(Object::Nil)),
-        [Object::Keyword(name), obj] if name == "map" => {
-            let new_items: Vec<Object> = items
+        [Object::Keyword(name)] if name == "last" => {
+            Some(items.last().cloned().unwrap_or(Object::Nil))
+        }
+        [Object::Keyword(name)] if name == "reverse" => {
+            Some(Object::List(items.iter().rev().cloned().collect()))
+        }
+        [Object::Keyword(name), Object::List(other)] if name == "concat" => {
+            Some(Object::List([items, other].concat()))
+        }
+        [Object::Operator(op), Object::List(other)] if op == "++" => {
+            Some(Object::List([items, other].concat()))
+        }
+        [Object::Keyword(name), Object::Int(n)] if name == "take" => {
+            Some(Object::List(items.iter().take(count(n)).cloned().collect()))
+        }
+        [Object::Keyword(name), Object::Int(n)] if name == "drop" => {
+            Some(Object::List(items.iter().skip(count(n)).cloned().collect()))
+        }
+        [Object::Keyword(name), Object::List(other)] if name == "zip" => Some(Object::List(
+            items
                 .iter()
-                .map(|item| {
-                    method_call(
-                        get_class_id(obj, env),
-                        get_object_properties(obj),
-                        &vec![item.to_owned()],
+                .zip(other)
+                .map(|(a, b)| Object::List(vec![a.clone(), b.clone()]))
+                .collect(),
+        )),
+        // without a comparator items are sorted with `<`
+        [Object::Keyword(name), cmp @ ..] if name == "sort" && cmp.len() <= 1 => {
+            let mut before = |a: &Object, b: &Object| {
+                let result = match cmp.first() {
+                    Some(cmp) => send_message(cmp, &[a.clone(), b.clone()], env, class_env),
+                    None => send_message(
+                        a,
+                        &[Object::Operator("<".to_owned()), b.clone()],
                         env,
                         class_env,
-                    )
+                    ),
+                };
+                is_truthy(&result, env)
+            };
+            Some(Object::List(merge_sort(items.to_vec(), &mut before)))
+        }
+        [Object::Keyword(name), sep @ ..] if name == "join" && sep.len() <= 1 => {
+            let sep = match sep.first() {
+                Some(Object::Str(sep)) => sep.to_owned(),
+                Some(other) => panic!("can't join with {}", other.to_s(class_env)),
+                None => "".to_owned(),
+            };
+            let strs: Vec<String> = items
+                .iter()
+                .map(|item| match item {
+                    Object::Str(str) => str.to_owned(),
+                    _ => to_display_s(item, env, class_env),
+                })
+                .collect();
+            Some(Object::Str(strs.join(&sep)))
+        }
+        [Object::Keyword(name), obj] if name == "each" => {
+            for item in items {
+                call(obj, item, env, class_env);
+            }
+            Some(Object::Nil)
+        }
+        [Object::Keyword(name), init, obj] if name == "fold" => {
+            Some(items.iter().fold(init.clone(), |acc, item| {
+                send_message(obj, &[acc, item.clone()], env, class_env)
+            }))
+        }
+        [Object::Keyword(name), obj] if name == "flat_map" => {
+            let mut new_items = vec![];
+            for item in items {
+                match call(obj, item, env, class_env) {
+                    Object::List(nested) => new_items.extend(nested),
+                    other => new_items.push(other),
+                }
+            }
+            Some(Object::List(new_items))
+        }
+        [Object::Keyword(name), obj] if name == "find" => Some(
+            items
+                .iter()
+                .find(|item| {
+                    let result = call(obj, item, env, class_env);
+                    is_truthy(&result, env)
                 })
+                .cloned()
+                .unwrap_or(Object::Nil),
+        ),
+        [Object::Keyword(name), obj] if name == "all?" => {
+            let result = items.iter().all(|item| {
+                let result = call(obj, item, env, class_env);
+                is_truthy(&result, env)
+            });
+            Some(bool_object(result, env))
+        }
+        [Object::Keyword(name), fs @ ..] if name == "map" && !fs.is_empty() => {
+            let new_items: Vec<Object> = items
+                .iter()
+                .map(|item| send_through(fs, item, env, class_env))
                 .collect();
 
             Some(Object::List(new_items))
         }
+        [Object::Keyword(name), obj] if name == "reduce" => Some(
+            items
+                .iter()
+                .cloned()
+                .reduce(|acc, item| send_message(obj, &[acc, item], env, class_env))
+                .unwrap_or(Object::Nil),
+        ),
         [Object::Keyword(name), obj] if name == "filter" => {
-            if let Some(Object::Class(true_class_id)) = env.get("TrueClass") {
-                let true_class_id = true_class_id.clone();
-                let new_items: Vec<Object> = items
-                    .iter()
-                    .filter(|item| {
-                        let result = method_call(
-                            get_class_id(obj, env),
-                            get_object_properties(obj),
-                            &vec![(*item).to_owned()],
-                            env,
-                            class_env,
-                        );
-                        match result {
-                            Object::Instance(class_id, _) => class_id == true_class_id,
-                            _ => false,
-                        }
-                    })
-                    .map(|item| item.clone())
-                    .collect();
+            let new_items: Vec<Object> = items
+                .iter()
+                .filter(|item| {
+                    let result = call(obj, item, env, class_env);
+                    is_truthy(&result, env)
+                })
+                .cloned()
+                .collect();
 
-                Some(Object::List(new_items))
-            } else {
-                panic!("ah")
-            }
+            Some(Object::List(new_items))
+        }
+        [Object::Keyword(name)] if name == "iter" => Some(Object::Iter(Box::new(Lazy::Items(
+            Rc::new(items.to_vec()),
+            0,
+        )))),
+        [Object::Keyword(name)] if name == "to_set" => {
+            Some(set_from(items.iter().cloned(), env, class_env))
         }
         [Object::Keyword(name), obj] if name == "any?" => {
-            if let Some(Object::Class(true_class_id)) = env.get("TrueClass") {
-                let true_class_id = true_class_id.clone();
-                let result = items.iter().any(|item| {
-                    let result = method_call(
-                        get_class_id(obj, env),
-                        get_object_properties(obj),
-                        &vec![(*item).to_owned()],
-                        env,
-                        class_env,
-                    );
-                    match result {
-                        Object::Instance(class_id, _) => class_id == true_class_id,
-                        _ => false,
-                    }
-                });
-                if result {
-                    Some(eval_node(
-                        &Node::IdLookup("true".to_string()),
-                        env,
-                        class_env,
-                    ))
-                } else {
-                    Some(eval_node(
-                        &Node::IdLookup("false".to_string()),
-                        env,
-                        class_env,
-                    ))
-                }
-            } else {
-                panic!("ah")
-            }
+            let result = items.iter().any(|item| {
+                let result = call(obj, item, env, class_env);
+                is_truthy(&result, env)
+            });
+            Some(bool_object(result, env))
         }
         _ => None,
     }
 }
 
-fn try_eval_native_keyword_fn(val: &String, args: &[Object]) -> Option<Object> {
-    match args {
-        [Object::Keyword(name)] if name == "log" => {
-            println!(":{}", val);
-            Some(Object::Nil)
-        }
-        _ => None,
+// the ints of a range, one at a time
+fn range_ints(start: i64, end: i64, inclusive: bool) -> std::ops::RangeInclusive<i64> {
+    match (inclusive, end.checked_sub(1)) {
+        (true, _) => start..=end,
+        (false, Some(last)) => start..=last,
+        // nothing comes before i64::MIN
+        (false, None) => std::ops::RangeInclusive::new(1, 0),
     }
 }
 
-fn try_eval_native_str_fn(lhs: &String, args: &[Object]) -> Option<Object> {
+fn try_eval_native_range_fn(
+    start: i64,
+    end: i64,
+    inclusive: bool,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    let ints = range_ints(start, end, inclusive);
     match args {
-        [Object::Keyword(name)] if name == "log" => {
-            println!("{}", lhs);
+        // `:map` & `:filter` come from `:iter`, so they're lazy
+        [Object::Keyword(name), obj] if name == "each" => {
+            for n in ints {
+                call(obj, &Object::Int(n), env, class_env);
+            }
             Some(Object::Nil)
         }
+        [Object::Keyword(name), Object::Int(n)] if name == "includes?" => {
+            Some(bool_object(ints.contains(n), env))
+        }
+        [Object::Keyword(name), _] if name == "includes?" => Some(bool_object(false, env)),
+        [Object::Keyword(name)] if name == "to_list" => {
+            Some(Object::List(ints.map(Object::Int).collect()))
+        }
+        [Object::Keyword(name)] if name == "iter" => {
+            Some(Object::Iter(Box::new(Lazy::Ints(start, end, inclusive))))
+        }
         _ => None,
     }
 }
 
-fn try_eval_native_int_fn(lhs: usize, args: &[Object]) -> Option<Object> {
-    match args {
-        [Object::Keyword(name)] if name == "log" => {
-            println!("{}", lhs);
-            Some(Object::Nil)
-        }
-        [Object::Operator(op), Object::Int(other_val)] if op == "+" => {
-            Some(Object::Int(lhs + other_val))
-        }
-        _ => None,
+// Ints don't wrap, going past i64 is an error
+fn arithmetic(lhs: i64, op: &str, rhs: i64) -> i64 {
+    if (op == "/" || op == "%") && rhs == 0 {
+        panic!("`{} {} {}` divides by zero", lhs, op, rhs)
     }
+    if op == "**" && rhs < 0 {
+        panic!("`{} ** {}` raises to a negative power", lhs, rhs)
+    }
+    let result = match op {
+        "+" => lhs.checked_add(rhs),
+        "-" => lhs.checked_sub(rhs),
+        "*" => lhs.checked_mul(rhs),
+        "/" => lhs.checked_div_euclid(rhs),
+        "%" => lhs.checked_rem_euclid(rhs),
+        _ => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_pow(rhs)),
+    };
+    result.unwrap_or_else(|| panic!("`{} {} {}` overflows an Int", lhs, op, rhs))
 }
 
-fn try_eval_native_nil_fn(args: &[Object]) -> Option<Object> {
+fn try_eval_native_int_fn(
+    lhs: i64,
+    args: &[Object],
+    env: &HashMap<String, Object>,
+) -> Option<Object> {
     match args {
-        [Object::Keyword(name)] if name == "log" => {
-            println!("nil");
-            Some(Object::Nil)
-        }
+        [Object::Operator(op), Object::Int(rhs)] => match op.as_str() {
+            "+" | "-" | "*" | "/" | "%" | "**" => Some(Object::Int(arithmetic(lhs, op, *rhs))),
+            ".." => Some(Object::Range(lhs, *rhs, false)),
+            "..=" => Some(Object::Range(lhs, *rhs, true)),
+            ">" => Some(bool_object(lhs > *rhs, env)),
+            "<" => Some(bool_object(lhs < *rhs, env)),
+            ">=" => Some(bool_object(lhs >= *rhs, env)),
+            "<=" => Some(bool_object(lhs <= *rhs, env)),
+            "==" => Some(bool_object(lhs == *rhs, env)),
+            _ => None,
+        },
         _ => None,
     }
 }
 
-fn try_eval_native_instance_fn(
-    id: &Uuid,
-    properties: &Vec<(String, Object)>,
+fn try_eval_native_operator_fn(
+    op: &String,
     args: &[Object],
-    _env: &HashMap<String, Object>,
-    class_env: &HashMap<Uuid, Class>,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
 ) -> Option<Object> {
     match args {
-        [Object::Keyword(name)] if name == "log" => {
-            println!(
-                "{}",
-                Object::Instance(*id, properties.clone()).to_s(class_env)
-            );
-            Some(Object::Nil)
-        }
+        // `+ :call 1 2` is the same as `1 + 2`
+        [Object::Keyword(name), lhs, rhs] if name == "call" => Some(send_message(
+            lhs,
+            &[Object::Operator(op.to_owned()), rhs.to_owned()],
+            env,
+            class_env,
+        )),
+        // so operators can be handed to `:reduce` like any other binary object
+        [lhs, rhs] => Some(send_message(
+            lhs,
+            &[Object::Operator(op.to_owned()), rhs.to_owned()],
+            env,
+            class_env,
+        )),
         _ => None,
     }
 }
 
-fn try_eval_native_fn(
-    lhs: &Object,
+fn try_eval_native_cell_fn(
+    cell: &Ref,
     args: &[Object],
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
 ) -> Option<Object> {
-    match lhs {
-        Object::Keyword(name) => try_eval_native_keyword_fn(name, args),
-        Object::Str(value) => try_eval_native_str_fn(value, args),
-        Object::Int(value) => try_eval_native_int_fn(*value, args),
-        Object::List(items) => try_eval_native_list_fn(items, args, env, class_env),
-        Object::Nil => try_eval_native_nil_fn(args),
-        Object::Instance(class_id, properties) => {
-            try_eval_native_instance_fn(class_id, properties, args, env, class_env)
+    match args {
+        [Object::Keyword(name)] if name == "get" => Some(cell.get()),
+        [Object::Keyword(name), val] if name == "set" => {
+            cell.set(val.clone());
+            Some(val.clone())
         }
-        Object::Class(_) => todo!(),
-        Object::Operator(_) => todo!(),
-    }
+        // counter :update object def n -> n + 1; end;
+        [Object::Keyword(name), f] if name == "update" => {
+            let val = call(f, &cell.get(), env, class_env);
+            cell.set(val.clone());
+            Some(val)
+        }
+        _ => None,
+    }
+}
+
+// what a native message takes after its selector, `More` lets any number of arguments follow
+#[derive(Clone, Copy)]
+enum Arg {
+    Any,
+    More,
+    Int,
+    Str,
+    List,
+    Dict,
+    Set,
+    IntOrRange,
+}
+
+// a selector & the arguments after it, "" is a message without one, like `[1; 2;] 0`
+type NativeMessage = (&'static str, &'static [Arg]);
+
+// messages every object answers, natively or with `try_eval_default_fn`; a wildcard
+// handler like `def n` doesn't swallow these
+const UNIVERSAL_MESSAGES: &[NativeMessage] = &[
+    ("to_s", &[]),
+    ("==", &[Arg::Any]),
+    ("hash", &[]),
+    ("log", &[]),
+    ("inspect", &[]),
+    ("identical?", &[Arg::Any]),
+    ("class", &[]),
+    ("fields", &[]),
+    ("responds_to?", &[Arg::Any, Arg::More]),
+    ("handlers", &[]),
+];
+
+// a plain value is a branch that's already been evaluated, `def n` may still take `:call`
+const DEFAULT_MESSAGES: &[NativeMessage] = &[("call", &[])];
+
+// what `try_eval_iter_fn` answers, for anything with an `:iter` or a `:next`
+const ITER_MESSAGES: &[NativeMessage] = &[
+    ("iter", &[]),
+    ("next", &[]),
+    ("to_list", &[]),
+    ("filter", &[Arg::Any]),
+    ("each", &[Arg::Any]),
+    ("reduce", &[Arg::Any]),
+    ("take", &[Arg::Int]),
+    ("map", &[Arg::Any, Arg::More]),
+];
+
+const INT_MESSAGES: &[NativeMessage] = &[
+    ("+", &[Arg::Int]),
+    ("-", &[Arg::Int]),
+    ("*", &[Arg::Int]),
+    ("/", &[Arg::Int]),
+    ("%", &[Arg::Int]),
+    ("**", &[Arg::Int]),
+    ("..", &[Arg::Int]),
+    ("..=", &[Arg::Int]),
+    (">", &[Arg::Int]),
+    ("<", &[Arg::Int]),
+    (">=", &[Arg::Int]),
+    ("<=", &[Arg::Int]),
+    ("==", &[Arg::Int]),
+];
+
+const LIST_MESSAGES: &[NativeMessage] = &[
+    ("", &[Arg::Int]),
+    ("len", &[]),
+    ("first", &[]),
+    ("last", &[]),
+    ("reverse", &[]),
+    ("iter", &[]),
+    ("to_set", &[]),
+    ("concat", &[Arg::List]),
+    ("zip", &[Arg::List]),
+    ("++", &[Arg::List]),
+    ("take", &[Arg::Int]),
+    ("drop", &[Arg::Int]),
+    ("sort", &[]),
+    ("sort", &[Arg::Any]),
+    ("join", &[]),
+    ("join", &[Arg::Any]),
+    ("each", &[Arg::Any]),
+    ("flat_map", &[Arg::Any]),
+    ("find", &[Arg::Any]),
+    ("all?", &[Arg::Any]),
+    ("any?", &[Arg::Any]),
+    ("reduce", &[Arg::Any]),
+    ("filter", &[Arg::Any]),
+    ("fold", &[Arg::Any, Arg::Any]),
+    ("map", &[Arg::Any, Arg::More]),
+];
+
+const RANGE_MESSAGES: &[NativeMessage] = &[
+    ("each", &[Arg::Any]),
+    ("includes?", &[Arg::Any]),
+    ("to_list", &[]),
+    ("iter", &[]),
+];
+
+const OPERATOR_MESSAGES: &[NativeMessage] =
+    &[("call", &[Arg::Any, Arg::Any]), ("", &[Arg::Any, Arg::Any])];
+
+const CELL_MESSAGES: &[NativeMessage] =
+    &[("get", &[]), ("set", &[Arg::Any]), ("update", &[Arg::Any])];
+
+const STR_MESSAGES: &[NativeMessage] = &[
+    ("+", &[Arg::Str]),
+    ("==", &[Arg::Str]),
+    (">", &[Arg::Str]),
+    ("<", &[Arg::Str]),
+    (">=", &[Arg::Str]),
+    ("<=", &[Arg::Str]),
+    ("", &[Arg::IntOrRange]),
+    ("len", &[]),
+    ("chars", &[]),
+    ("lines", &[]),
+    ("split", &[]),
+    ("trim", &[]),
+    ("upcase", &[]),
+    ("downcase", &[]),
+    ("to_i", &[]),
+    ("iter", &[]),
+    ("split", &[Arg::Str]),
+    ("starts_with?", &[Arg::Str]),
+    ("ends_with?", &[Arg::Str]),
+    ("contains?", &[Arg::Str]),
+    ("replace", &[Arg::Str, Arg::Str]),
+];
+
+const DICT_MESSAGES: &[NativeMessage] = &[
+    ("get", &[Arg::Any]),
+    ("has?", &[Arg::Any]),
+    ("remove", &[Arg::Any]),
+    ("map", &[Arg::Any]),
+    ("filter", &[Arg::Any]),
+    ("put", &[Arg::Any, Arg::Any]),
+    ("len", &[]),
+    ("keys", &[]),
+    ("values", &[]),
+    ("entries", &[]),
+    ("iter", &[]),
+    ("merge", &[Arg::Dict]),
+];
+
+const SET_MESSAGES: &[NativeMessage] = &[
+    ("len", &[]),
+    ("to_list", &[]),
+    ("iter", &[]),
+    ("includes?", &[Arg::Any]),
+    ("add", &[Arg::Any]),
+    ("remove", &[Arg::Any]),
+    ("each", &[Arg::Any]),
+    ("filter", &[Arg::Any]),
+    ("map", &[Arg::Any, Arg::More]),
+    ("|", &[Arg::Set]),
+    ("&", &[Arg::Set]),
+    ("-", &[Arg::Set]),
+];
+
+const SYM_MESSAGES: &[NativeMessage] = &[("desc", &[])];
+
+// `V[1; 2;] 0`, only for instances built from a vector
+const INSTANCE_MESSAGES: &[NativeMessage] = &[("", &[Arg::Int])];
+
+// the natives of each type, `try_eval_native_fn` only runs what's listed here
+fn native_messages(lhs: &Object, class_env: &HashMap<Uuid, Class>) -> &'static [NativeMessage] {
+    match lhs {
+        Object::Instance(id, props) if positional_values(id, props, class_env).is_some() => {
+            INSTANCE_MESSAGES
+        }
+        Object::Int(_) => INT_MESSAGES,
+        Object::List(_) => LIST_MESSAGES,
+        Object::Range(..) => RANGE_MESSAGES,
+        Object::Operator(_) => OPERATOR_MESSAGES,
+        Object::Cell(_) => CELL_MESSAGES,
+        Object::Str(_) => STR_MESSAGES,
+        Object::Dict(_) => DICT_MESSAGES,
+        Object::Set(_) => SET_MESSAGES,
+        Object::Sym(..) => SYM_MESSAGES,
+        Object::Iter(_) => ITER_MESSAGES,
+        Object::Keyword(_)
+        | Object::Nil
+        | Object::Instance(..)
+        | Object::Class(_)
+        | Object::Placeholder(_) => &[],
+    }
+}
+
+// is `args` one of `messages`
+fn lists_message(messages: &[NativeMessage], args: &[Object]) -> bool {
+    messages.iter().any(|(selector, takes)| match args {
+        _ if selector.is_empty() => takes_args(takes, args),
+        [Object::Keyword(name) | Object::Operator(name), rest @ ..] if name == selector => {
+            takes_args(takes, rest)
+        }
+        _ => false,
+    })
+}
+
+fn takes_args(takes: &[Arg], args: &[Object]) -> bool {
+    match (takes, args) {
+        ([Arg::More], _) | ([], []) => true,
+        ([take, takes @ ..], [arg, args @ ..]) => {
+            let fits = matches!(
+                (take, arg),
+                (Arg::Any, _)
+                    | (Arg::Int, Object::Int(_))
+                    | (Arg::Str, Object::Str(_))
+                    | (Arg::List, Object::List(_))
+                    | (Arg::Dict, Object::Dict(_))
+                    | (Arg::Set, Object::Set(_))
+                    | (Arg::IntOrRange, Object::Int(_) | Object::Range(..))
+            );
+            fits && takes_args(takes, args)
+        }
+        _ => false,
+    }
+}
+
+fn try_eval_universal_fn(
+    lhs: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    match args {
+        [Object::Keyword(name)] if name == "log" => {
+            match lhs {
+                Object::Str(str) => println!("{}", str),
+                _ => println!("{}", to_display_s(lhs, env, class_env)),
+            }
+            Some(Object::Nil)
+        }
+        [Object::Keyword(name)] if name == "inspect" => Some(Object::Str(lhs.to_s(class_env))),
+        // unlike `==`, cells are only identical to themselves
+        [Object::Keyword(name), rhs] if name == "identical?" => Some(bool_object(lhs == rhs, env)),
+        [Object::Keyword(name)] if name == "class" => Some(Object::Class(get_class_id(lhs, env))),
+        [Object::Keyword(name)] if name == "fields" => {
+            Some(Object::List(fields_of(lhs, class_env)))
+        }
+        // `p :responds_to? :move 1 2;` asks about `p :move 1 2;` without sending it
+        // & `p :responds_to? :native :to_s;` about the natives alone
+        [Object::Keyword(name), message @ ..] if name == "responds_to?" && !message.is_empty() => {
+            let (native, message) = match message {
+                [Object::Keyword(name), rest @ ..] if name == "native" => (true, rest),
+                _ => (false, message),
+            };
+            let found = (!native
+                && (try_eval_property_lookup(&get_object_properties(lhs), message).is_some()
+                    || responds_to(lhs, message, env, class_env)))
+                || native_answers(lhs, message, class_env)
+                || user_iterator_answers(lhs, message, env, class_env);
+            Some(bool_object(found, env))
+        }
+        [Object::Keyword(name)] if name == "handlers" => {
+            Some(Object::List(handlers_of(lhs, env, class_env)))
+        }
+        _ => None,
+    }
+}
+
+fn try_eval_native_fn(
+    lhs: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    if let Some(val) = try_eval_universal_fn(lhs, args, env, class_env) {
+        return Some(val);
+    }
+    if !lists_message(native_messages(lhs, class_env), args) {
+        return None;
+    }
+
+    match lhs {
+        Object::Int(value) => try_eval_native_int_fn(*value, args, env),
+        Object::List(items) => try_eval_native_list_fn(items, args, env, class_env),
+        Object::Operator(op) => try_eval_native_operator_fn(op, args, env, class_env),
+        Object::Cell(cell) => try_eval_native_cell_fn(cell, args, env, class_env),
+        Object::Range(start, end, inclusive) => {
+            try_eval_native_range_fn(*start, *end, *inclusive, args, env, class_env)
+        }
+        Object::Iter(_) => try_eval_iter_fn(lhs, args, env, class_env),
+        Object::Str(str) => try_eval_native_str_fn(str, args, env),
+        Object::Dict(dict) => try_eval_native_dict_fn(dict, args, env, class_env),
+        Object::Set(set) => try_eval_native_set_fn(set, args, env, class_env),
+        Object::Sym(_, desc) => match args {
+            [Object::Keyword(name)] if name == "desc" => Some(
+                desc.as_ref()
+                    .map(|desc| Object::Keyword(desc.to_owned()))
+                    .unwrap_or(Object::Nil),
+            ),
+            _ => None,
+        },
+        Object::Instance(id, props) => match args {
+            [Object::Int(idx)] => positional_values(id, props, class_env).map(|values| {
+                index_of(*idx, values.len())
+                    .map(|idx| values[idx].clone())
+                    .unwrap_or(Object::Nil)
+            }),
+            _ => None,
+        },
+        Object::Keyword(_) | Object::Nil | Object::Class(_) | Object::Placeholder(_) => None,
+    }
+}
+
+// would a native or default answer `args`, checked without sending them
+fn native_answers(lhs: &Object, args: &[Object], class_env: &HashMap<Uuid, Class>) -> bool {
+    let messages = native_messages(lhs, class_env);
+    // natives with an `:iter` get the rest of the protocol from `try_eval_iter_fn`
+    let iterable = lists_message(messages, &[Object::Keyword("iter".to_owned())]);
+    lists_message(UNIVERSAL_MESSAGES, args)
+        || lists_message(DEFAULT_MESSAGES, args)
+        || (args.len() == 1 && has_placeholders(lhs))
+        || lists_message(messages, args)
+        || (iterable && lists_message(ITER_MESSAGES, args))
+}
+
+// classes with `def :next` or `def :iter` get the rest of the protocol
+fn user_iterator_answers(
+    lhs: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> bool {
+    lists_message(ITER_MESSAGES, args)
+        && ["next", "iter"]
+            .iter()
+            .any(|name| responds_to(lhs, &[Object::Keyword(name.to_string())], env, class_env))
+}
+
+fn try_eval_native_str_fn(
+    str: &str,
+    args: &[Object],
+    env: &HashMap<String, Object>,
+) -> Option<Object> {
+    let graphemes: Vec<&str> = str.graphemes(true).collect();
+    let strs =
+        |strs: Vec<&str>| Object::List(strs.iter().map(|s| Object::Str(s.to_string())).collect());
+    match args {
+        [Object::Operator(op), Object::Str(rhs)] => match op.as_str() {
+            "+" => Some(Object::Str(format!("{}{}", str, rhs))),
+            "==" => Some(bool_object(str == rhs, env)),
+            ">" => Some(bool_object(str > rhs.as_str(), env)),
+            "<" => Some(bool_object(str < rhs.as_str(), env)),
+            ">=" => Some(bool_object(str >= rhs.as_str(), env)),
+            "<=" => Some(bool_object(str <= rhs.as_str(), env)),
+            _ => None,
+        },
+        [Object::Int(idx)] => Some(
+            index_of(*idx, graphemes.len())
+                .map(|idx| Object::Str(graphemes[idx].to_owned()))
+                .unwrap_or(Object::Nil),
+        ),
+        // "hello" (1..3;) is "el"
+        [Object::Range(start, end, inclusive)] => {
+            let len = graphemes.len() as i64;
+            let clamp = |idx: i64| (if idx < 0 { len + idx } else { idx }).clamp(0, len) as usize;
+            let start = clamp(*start);
+            let end = clamp(if *inclusive {
+                end.saturating_add(1)
+            } else {
+                *end
+            })
+            .max(start);
+            Some(Object::Str(graphemes[start..end].concat()))
+        }
+        [Object::Keyword(name)] if name == "len" => Some(Object::Int(graphemes.len() as i64)),
+        [Object::Keyword(name)] if name == "chars" => Some(strs(graphemes)),
+        [Object::Keyword(name)] if name == "lines" => Some(strs(str.lines().collect())),
+        [Object::Keyword(name)] if name == "split" => Some(strs(str.split_whitespace().collect())),
+        [Object::Keyword(name), Object::Str(sep)] if name == "split" => {
+            Some(strs(str.split(sep.as_str()).collect()))
+        }
+        [Object::Keyword(name)] if name == "trim" => Some(Object::Str(str.trim().to_owned())),
+        [Object::Keyword(name)] if name == "upcase" => Some(Object::Str(str.to_uppercase())),
+        [Object::Keyword(name)] if name == "downcase" => Some(Object::Str(str.to_lowercase())),
+        [Object::Keyword(name), Object::Str(prefix)] if name == "starts_with?" => {
+            Some(bool_object(str.starts_with(prefix.as_str()), env))
+        }
+        [Object::Keyword(name), Object::Str(suffix)] if name == "ends_with?" => {
+            Some(bool_object(str.ends_with(suffix.as_str()), env))
+        }
+        [Object::Keyword(name), Object::Str(part)] if name == "contains?" => {
+            Some(bool_object(str.contains(part.as_str()), env))
+        }
+        [Object::Keyword(name), Object::Str(from), Object::Str(to)] if name == "replace" => {
+            Some(Object::Str(str.replace(from.as_str(), to)))
+        }
+        [Object::Keyword(name)] if name == "to_i" => Some(match str.trim().parse::<i64>() {
+            Ok(val) => ok_object(Object::Int(val), env),
+            Err(_) => err_object(Object::Str(format!("`{}` isn't an integer", str)), env),
+        }),
+        [Object::Keyword(name)] if name == "iter" => Some(Object::Iter(Box::new(Lazy::Items(
+            Rc::new(
+                graphemes
+                    .iter()
+                    .map(|s| Object::Str(s.to_string()))
+                    .collect(),
+            ),
+            0,
+        )))),
+        _ => None,
+    }
+}
+
+fn dict_find(
+    dict: &Dict,
+    key: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<usize> {
+    let hash = hash_object(key, env, class_env);
+    dict.index
+        .get(&hash)?
+        .iter()
+        .copied()
+        .find(|idx| objects_equal(&dict.entries[*idx].0.clone(), key, env, class_env))
+}
+
+fn dict_insert(
+    dict: &mut Dict,
+    key: Object,
+    val: Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) {
+    match dict_find(dict, &key, env, class_env) {
+        Some(idx) => dict.entries[idx].1 = val,
+        None => {
+            let hash = hash_object(&key, env, class_env);
+            dict.index.entry(hash).or_default().push(dict.entries.len());
+            dict.entries.push((key, val));
+        }
+    }
+}
+
+fn dict_from(
+    entries: impl IntoIterator<Item = (Object, Object)>,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    let mut dict = Dict::default();
+    for (key, val) in entries {
+        dict_insert(&mut dict, key, val, env, class_env);
+    }
+    Object::Dict(dict)
+}
+
+// dicts are values, `:put` & `:remove` return a changed copy
+fn try_eval_native_dict_fn(
+    dict: &Dict,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    let entries = dict.entries.iter().cloned();
+    match args {
+        [Object::Keyword(name), key] if name == "get" => Some(
+            dict_find(dict, key, env, class_env)
+                .map(|idx| dict.entries[idx].1.clone())
+                .unwrap_or(Object::Nil),
+        ),
+        [Object::Keyword(name), key] if name == "has?" => {
+            let found = dict_find(dict, key, env, class_env).is_some();
+            Some(bool_object(found, env))
+        }
+        [Object::Keyword(name), key, val] if name == "put" => {
+            let mut dict = dict.clone();
+            dict_insert(&mut dict, key.clone(), val.clone(), env, class_env);
+            Some(Object::Dict(dict))
+        }
+        [Object::Keyword(name), key] if name == "remove" => {
+            let idx = dict_find(dict, key, env, class_env);
+            let entries = entries
+                .enumerate()
+                .filter(|(i, _)| Some(*i) != idx)
+                .map(|(_, entry)| entry);
+            Some(dict_from(entries, env, class_env))
+        }
+        [Object::Keyword(name)] if name == "len" => Some(Object::Int(dict.entries.len() as i64)),
+        [Object::Keyword(name)] if name == "keys" => {
+            Some(Object::List(entries.map(|(key, _)| key).collect()))
+        }
+        [Object::Keyword(name)] if name == "values" => {
+            Some(Object::List(entries.map(|(_, val)| val).collect()))
+        }
+        [Object::Keyword(name)] if name == "entries" => Some(Object::List(
+            entries
+                .map(|(key, val)| Object::List(vec![key, val]))
+                .collect(),
+        )),
+        [Object::Keyword(name)] if name == "iter" => Some(Object::Iter(Box::new(Lazy::Items(
+            Rc::new(
+                entries
+                    .map(|(key, val)| Object::List(vec![key, val]))
+                    .collect(),
+            ),
+            0,
+        )))),
+        // `f` is sent each key & value, what it returns is the new value
+        [Object::Keyword(name), f] if name == "map" => {
+            let entries: Vec<(Object, Object)> = entries
+                .map(|(key, val)| {
+                    let val = send_message(f, &[key.clone(), val], env, class_env);
+                    (key, val)
+                })
+                .collect();
+            Some(dict_from(entries, env, class_env))
+        }
+        [Object::Keyword(name), f] if name == "filter" => {
+            let entries: Vec<(Object, Object)> = entries
+                .filter(|(key, val)| {
+                    let keep = send_message(f, &[key.clone(), val.clone()], env, class_env);
+                    is_truthy(&keep, env)
+                })
+                .collect();
+            Some(dict_from(entries, env, class_env))
+        }
+        // entries from `other` win
+        [Object::Keyword(name), Object::Dict(other)] if name == "merge" => {
+            let mut dict = dict.clone();
+            for (key, val) in other.entries.iter().cloned() {
+                dict_insert(&mut dict, key, val, env, class_env);
+            }
+            Some(Object::Dict(dict))
+        }
+        _ => None,
+    }
+}
+
+fn set_from(
+    items: impl IntoIterator<Item = Object>,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    let mut set = Dict::default();
+    for item in items {
+        dict_insert(&mut set, item, Object::Nil, env, class_env);
+    }
+    Object::Set(set)
+}
+
+// items keep the order they were first added in, so sets print the same every time
+fn try_eval_native_set_fn(
+    set: &Dict,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    let items: Vec<Object> = set.entries.iter().map(|(item, _)| item.clone()).collect();
+    match args {
+        [Object::Keyword(name)] if name == "len" => Some(Object::Int(items.len() as i64)),
+        [Object::Keyword(name), item] if name == "includes?" => {
+            let found = dict_find(set, item, env, class_env).is_some();
+            Some(bool_object(found, env))
+        }
+        [Object::Keyword(name), item] if name == "add" => {
+            let mut set = set.clone();
+            dict_insert(&mut set, item.clone(), Object::Nil, env, class_env);
+            Some(Object::Set(set))
+        }
+        [Object::Keyword(name), item] if name == "remove" => {
+            let items: Vec<Object> = items
+                .into_iter()
+                .filter(|other| !objects_equal(other, item, env, class_env))
+                .collect();
+            Some(set_from(items, env, class_env))
+        }
+        [Object::Operator(op), Object::Set(other)] if op == "|" => {
+            let other = other.entries.iter().map(|(item, _)| item.clone());
+            Some(set_from(items.into_iter().chain(other), env, class_env))
+        }
+        [Object::Operator(op), Object::Set(other)] if op == "&" || op == "-" => {
+            let keep_found = op == "&";
+            let items: Vec<Object> = items
+                .into_iter()
+                .filter(|item| dict_find(other, item, env, class_env).is_some() == keep_found)
+                .collect();
+            Some(set_from(items, env, class_env))
+        }
+        [Object::Keyword(name)] if name == "to_list" => Some(Object::List(items)),
+        [Object::Keyword(name)] if name == "iter" => {
+            Some(Object::Iter(Box::new(Lazy::Items(Rc::new(items), 0))))
+        }
+        [Object::Keyword(name), obj] if name == "each" => {
+            for item in &items {
+                call(obj, item, env, class_env);
+            }
+            Some(Object::Nil)
+        }
+        [Object::Keyword(name), fs @ ..] if name == "map" && !fs.is_empty() => {
+            let items: Vec<Object> = items
+                .iter()
+                .map(|item| send_through(fs, item, env, class_env))
+                .collect();
+            Some(set_from(items, env, class_env))
+        }
+        [Object::Keyword(name), obj] if name == "filter" => {
+            let items: Vec<Object> = items
+                .into_iter()
+                .filter(|item| {
+                    let keep = call(obj, item, env, class_env);
+                    is_truthy(&keep, env)
+                })
+                .collect();
+            Some(set_from(items, env, class_env))
+        }
+        _ => None,
+    }
+}
+
+fn ok_object(value: Object, env: &HashMap<String, Object>) -> Object {
+    match env.get("Ok") {
+        Some(Object::Class(id)) => Object::Instance(*id, vec![("value".to_owned(), value)]),
+        _ => panic!("Couldn't find Ok class"),
+    }
+}
+
+fn err_object(error: Object, env: &HashMap<String, Object>) -> Object {
+    match env.get("Err") {
+        Some(Object::Class(id)) => Object::Instance(*id, vec![("error".to_owned(), error)]),
+        _ => panic!("Couldn't find Err class"),
+    }
+}
+
+fn iter_result(value: Object, rest: Object, env: &HashMap<String, Object>) -> Object {
+    match env.get("IterResult") {
+        Some(Object::Class(id)) => Object::Instance(
+            *id,
+            vec![("rest".to_owned(), rest), ("value".to_owned(), value)],
+        ),
+        _ => panic!("Couldn't find IterResult class"),
+    }
+}
+
+fn iter_end(env: &HashMap<String, Object>) -> Object {
+    match env.get("IterEnd") {
+        Some(Object::Class(id)) => Object::Instance(*id, vec![]),
+        _ => panic!("Couldn't find IterEnd class"),
+    }
+}
+
+// the next value & the iterator for the rest, `None` once it has ended
+fn iter_next(
+    iter: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<(Object, Object)> {
+    let lazy = match iter {
+        Object::Iter(lazy) => lazy,
+        _ => {
+            let result = send_message(iter, &[Object::Keyword("next".to_owned())], env, class_env);
+            return match &result {
+                Object::Instance(id, props)
+                    if is_instance_of(id, &"IterResult".to_owned(), class_env) =>
+                {
+                    let mut values = positional_values(id, props, class_env).unwrap().into_iter();
+                    Some((values.next().unwrap(), values.next().unwrap()))
+                }
+                Object::Instance(id, _) if is_instance_of(id, &"IterEnd".to_owned(), class_env) => {
+                    None
+                }
+                _ => panic!(
+                    "`:next` returned {}, not IterResult[value; rest;] or IterEnd[]",
+                    result.to_s(class_env)
+                ),
+            };
+        }
+    };
+    let lazy_iter = |lazy: Lazy| Object::Iter(Box::new(lazy));
+    match lazy.as_ref() {
+        Lazy::Items(items, idx) => items
+            .get(*idx)
+            .map(|item| (item.clone(), lazy_iter(Lazy::Items(items.clone(), idx + 1)))),
+        Lazy::Ints(start, end, inclusive) => range_ints(*start, *end, *inclusive).next().map(|n| {
+            (
+                Object::Int(n),
+                match n.checked_add(1) {
+                    Some(next) => lazy_iter(Lazy::Ints(next, *end, *inclusive)),
+                    // i64::MAX was the last one
+                    None => lazy_iter(Lazy::Ints(n, n, false)),
+                },
+            )
+        }),
+        Lazy::Map(source, f) => iter_next(source, env, class_env).map(|(value, rest)| {
+            (
+                call(f, &value, env, class_env),
+                lazy_iter(Lazy::Map(rest, f.clone())),
+            )
+        }),
+        Lazy::Filter(source, f) => {
+            let mut source = source.clone();
+            while let Some((value, rest)) = iter_next(&source, env, class_env) {
+                let keep = call(f, &value, env, class_env);
+                if is_truthy(&keep, env) {
+                    return Some((value, lazy_iter(Lazy::Filter(rest, f.clone()))));
+                }
+                source = rest;
+            }
+            None
+        }
+        Lazy::Take(_, 0) => None,
+        Lazy::Take(source, n) => iter_next(source, env, class_env)
+            .map(|(value, rest)| (value, lazy_iter(Lazy::Take(rest, n - 1)))),
+    }
+}
+
+// what anything that answers `:next` can be sent, `:map`, `:filter` & `:take` stay lazy
+fn try_eval_iter_fn(
+    iter: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    if !lists_message(ITER_MESSAGES, args) {
+        return None;
+    }
+    let lazy = |lazy: Lazy| Some(Object::Iter(Box::new(lazy)));
+    match args {
+        [Object::Keyword(name)] if name == "iter" => Some(iter.clone()),
+        [Object::Keyword(name)] if name == "next" => Some(match iter_next(iter, env, class_env) {
+            Some((value, rest)) => iter_result(value, rest, env),
+            None => iter_end(env),
+        }),
+        [Object::Keyword(name), fs @ ..] if name == "map" && !fs.is_empty() => {
+            let map = fs.iter().fold(iter.clone(), |iter, f| {
+                Object::Iter(Box::new(Lazy::Map(iter, f.clone())))
+            });
+            Some(map)
+        }
+        [Object::Keyword(name), f] if name == "filter" => {
+            lazy(Lazy::Filter(iter.clone(), f.clone()))
+        }
+        [Object::Keyword(name), Object::Int(n)] if name == "take" => {
+            lazy(Lazy::Take(iter.clone(), usize::try_from(*n).unwrap_or(0)))
+        }
+        [Object::Keyword(name), f] if name == "each" => {
+            let mut iter = iter.clone();
+            while let Some((value, rest)) = iter_next(&iter, env, class_env) {
+                call(f, &value, env, class_env);
+                iter = rest;
+            }
+            Some(Object::Nil)
+        }
+        [Object::Keyword(name), f] if name == "reduce" => {
+            let Some((mut acc, mut iter)) = iter_next(iter, env, class_env) else {
+                return Some(Object::Nil);
+            };
+            while let Some((value, rest)) = iter_next(&iter, env, class_env) {
+                acc = send_message(f, &[acc, value], env, class_env);
+                iter = rest;
+            }
+            Some(acc)
+        }
+        [Object::Keyword(name)] if name == "to_list" => {
+            let mut items = vec![];
+            let mut iter = iter.clone();
+            while let Some((value, rest)) = iter_next(&iter, env, class_env) {
+                items.push(value);
+                iter = rest;
+            }
+            Some(Object::List(items))
+        }
+        _ => None,
+    }
+}
+
+// what `:to_s`, `==` & `:hash` fall back to when no class in the chain defines them
+fn try_eval_default_fn(
+    receiver: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Option<Object> {
+    match args {
+        [Object::Keyword(name)] if name == "to_s" => match receiver {
+            Object::Str(_) => Some(receiver.clone()),
+            // `5 :native :to_s` skips a `def :to_s` on Int
+            _ => Some(Object::Str(native_display_s_seen(
+                receiver,
+                env,
+                class_env,
+                &mut vec![],
+            ))),
+        },
+        [Object::Operator(op), rhs] if op == "==" => Some(bool_object(
+            structurally_equal(receiver, rhs, env, class_env),
+            env,
+        )),
+        [Object::Keyword(name)] if name == "hash" => {
+            Some(Object::Int(hash_object(receiver, env, class_env)))
+        }
+        // a plain value is a branch that's already been evaluated
+        [Object::Keyword(name)] if name == "call" => Some(receiver.clone()),
+        [arg] if has_placeholders(receiver) => {
+            Some(fill_placeholders(receiver, arg, env, class_env))
+        }
+        // user iterators answer `:next`, user collections answer `:iter`
+        _ if responds_to(
+            receiver,
+            &[Object::Keyword("next".to_owned())],
+            env,
+            class_env,
+        ) =>
+        {
+            try_eval_iter_fn(receiver, args, env, class_env)
+        }
+        _ if responds_to(
+            receiver,
+            &[Object::Keyword("iter".to_owned())],
+            env,
+            class_env,
+        ) =>
+        {
+            let iter = send_message(
+                receiver,
+                &[Object::Keyword("iter".to_owned())],
+                env,
+                class_env,
+            );
+            try_eval_iter_fn(&iter, args, env, class_env)
+        }
+        // natives with an `:iter`, like Range & Str, get the rest of the protocol too
+        _ => match try_eval_native_fn(
+            receiver,
+            &[Object::Keyword("iter".to_owned())],
+            env,
+            class_env,
+        ) {
+            Some(iter @ Object::Iter(_)) if !matches!(receiver, Object::Iter(_)) => {
+                try_eval_iter_fn(&iter, args, env, class_env)
+            }
+            _ => None,
+        },
+    }
+}
+
+// does any class in the receiver's chain have a handler for `args`
+fn responds_to(
+    receiver: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> bool {
+    let mut class_id = Some(get_class_id(receiver, env));
+    while let Some(id) = class_id {
+        if find_method_for(id, receiver, args, env, class_env).is_some() {
+            return true;
+        }
+        class_id = class_env.get(&id).and_then(|class| class.superclass);
+    }
+    false
+}
+
+// is the handler `args` would run written for `args[0]`, like `def :to_s`,
+// rather than a wildcard like `def n` that happens to match it
+fn defines_selector(
+    receiver: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> bool {
+    let mut class_id = Some(get_class_id(receiver, env));
+    while let Some(id) = class_id {
+        if let Some(def) = find_method_for(id, receiver, args, env, class_env) {
+            let Node::Def(patterns, _, _, _) = &*def else {
+                unreachable!()
+            };
+            return match (patterns.first(), args.first()) {
+                (Some(Node::Keyword(a)), Some(Object::Keyword(b))) => a == b,
+                (Some(Node::Operator(a)), Some(Object::Operator(b))) => a == b,
+                // `def ^step` only matches the value it pins
+                (Some(Node::Unquote(_)), Some(_)) => true,
+                _ => false,
+            };
+        }
+        class_id = class_env.get(&id).and_then(|class| class.superclass);
+    }
+    false
+}
+
+// is there a `def :name ...` up the receiver's chain, found without matching any patterns
+fn has_handler_named(
+    receiver: &Object,
+    name: &str,
+    env: &HashMap<String, Object>,
+    class_env: &HashMap<Uuid, Class>,
+) -> bool {
+    let mut class_id = Some(get_class_id(receiver, env));
+    while let Some(class) = class_id.and_then(|id| class_env.get(&id)) {
+        let named = class.methods.iter().any(|method| match &**method {
+            Node::Def(patterns, _, _, _) => {
+                matches!(patterns.first(), Some(Node::Keyword(selector)) if selector == name)
+            }
+            _ => false,
+        });
+        if named {
+            return true;
+        }
+        class_id = class.superclass;
+    }
+    false
+}
+
+// how `:log` shows an object, elements with their own `def :to_s` use it
+fn to_display_s(
+    object: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> String {
+    display_s_seen(object, env, class_env, &mut vec![])
+}
+
+fn display_s_seen(
+    object: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+    seen: &mut Vec<Ref>,
+) -> String {
+    let to_s = [Object::Keyword("to_s".to_owned())];
+    if defines_selector(object, &to_s, env, class_env) {
+        // a `:to_s` that doesn't give a Str falls back to the native format
+        if let Object::Str(str) = send_message(object, &to_s, env, class_env) {
+            return str;
+        }
+    }
+    native_display_s_seen(object, env, class_env, seen)
+}
+
+// the built-in format, only the elements use their own `:to_s`
+fn native_display_s_seen(
+    object: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+    seen: &mut Vec<Ref>,
+) -> String {
+    if let Object::Cell(cell) = object {
+        if seen.contains(cell) {
+            return "Cell[...]".to_owned();
+        }
+        seen.push(cell.clone());
+    }
+    let elements: Vec<String> = object
+        .elements()
+        .iter()
+        .map(|element| display_s_seen(element, env, class_env, seen))
+        .collect();
+    if let Object::Cell(_) = object {
+        seen.pop();
+    }
+    object.format(class_env, &elements)
 }
 
 fn set_env_from_record(
     r_props: &Vec<(String, Node)>,
     arg: &Object,
     env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
 ) {
     if let Object::Instance(_, o_props) = arg {
         let hash: HashMap<String, Object> = HashMap::from_iter(o_props.clone());
         for (name, pattern) in r_props {
-            if let Some(value) = hash.get(name) {
-                set_env_from_pattern(pattern, value, env);
+            if let Some(value) = hash.get(&prop_key(name, env)) {
+                set_env_from_pattern(pattern, value, env, class_env);
             }
         }
     } else {
@@ -445,197 +1879,263 @@ fn set_env_from_record(
     }
 }
 
-fn set_env_from_pattern(pattern: &Node, arg: &Object, env: &mut HashMap<String, Object>) {
+fn set_env_from_pattern(
+    pattern: &Node,
+    arg: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) {
     match pattern {
+        Node::IdLookup(name) if name == "_" => (),
         Node::IdLookup(name) => {
             env.insert(name.to_owned(), arg.to_owned());
         }
         Node::Keyword(_) => (),
-        Node::Class(_, _) => panic!(),
+        Node::Class(_, _, _, _) => panic!(),
         Node::MethodCall(_, _) => panic!(),
-        Node::RecordConstructor(_, r_props) => set_env_from_record(r_props, arg, env),
+        Node::RecordConstructor(_, r_props) => set_env_from_record(r_props, arg, env, class_env),
+        Node::RecordUpdate(_, _, _) => panic!(),
         Node::Int(_) => (),
         Node::Assign(_, _) => panic!(),
         Node::Operator(_) => (),
         Node::List(nodes) => {
             if let Object::List(objs) = arg {
-                set_env_from_patterns(nodes, objs, env)
+                set_env_from_patterns(nodes, objs, env, class_env)
             } else {
                 panic!("!");
             }
         }
-        Node::Def(_, _) => todo!(),
+        Node::Def(_, _, _, _) => todo!(),
         Node::Str(_) => (),
-        Node::VectorConstructor(_, exprs) => {
-            assert!(exprs.len() == 1);
-            if let [Node::IdLookup(name)] = exprs.as_slice() {
-                env.insert(name.to_owned(), arg.to_owned());
-            } else {
-                panic!("vector failure")
+        Node::VectorConstructor(_, exprs) => match arg {
+            Object::Cell(cell) => set_env_from_patterns(exprs, &[cell.get()], env, class_env),
+            Object::Instance(class_id, props) => {
+                let objs = positional_values(class_id, props, class_env).unwrap();
+                set_env_from_patterns(exprs, &objs, env, class_env)
+            }
+            _ => set_env_from_patterns(exprs, std::slice::from_ref(arg), env, class_env),
+        },
+        Node::RecordLiteral(r_props) => set_env_from_record(r_props, arg, env, class_env),
+        Node::Dict(entries) => {
+            if let Object::Dict(dict) = arg {
+                for (key, pattern) in entries {
+                    let key = eval_node(key, env, class_env);
+                    if let Some(idx) = dict_find(dict, &key, env, class_env) {
+                        set_env_from_pattern(pattern, &dict.entries[idx].1, env, class_env);
+                    }
+                }
             }
         }
-        Node::RecordLiteral(r_props) => set_env_from_record(r_props, arg, env),
         Node::Unquote(_) => (),
-        Node::ParenExpr(_) => todo!(),
-        Node::Spread(_) => todo!(),
+        Node::ParenExpr(node) => set_env_from_pattern(node, arg, env, class_env),
+        Node::Or(alternatives) => {
+            // bind from the first alternative that matched
+            let alternative = alternatives.iter().find(|alternative| {
+                match_pattern(
+                    alternative,
+                    arg,
+                    &mut env.clone(),
+                    class_env,
+                    &mut HashMap::new(),
+                )
+            });
+            if let Some(alternative) = alternative {
+                set_env_from_pattern(alternative, arg, env, class_env);
+            }
+        }
+        Node::As(name, pattern) => {
+            env.insert(name.to_owned(), arg.to_owned());
+            set_env_from_pattern(pattern, arg, env, class_env);
+        }
+        Node::Spread(node) => set_env_from_pattern(node, arg, env, class_env),
         Node::Object(_) => todo!(),
     }
 }
 
-fn set_env_from_patterns(patterns: &[Node], args: &[Object], env: &mut HashMap<String, Object>) {
-    for (pattern, arg) in patterns.iter().zip(args) {
-        set_env_from_pattern(pattern, arg, env);
+fn set_env_from_patterns(
+    patterns: &[Node],
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) {
+    match split_around_spread(patterns) {
+        Some((before, after)) => {
+            let rest_end = args.len() - after;
+            set_env_from_patterns(&patterns[..before], &args[..before], env, class_env);
+            set_env_from_pattern(
+                &patterns[before],
+                &Object::List(args[before..rest_end].to_vec()),
+                env,
+                class_env,
+            );
+            set_env_from_patterns(&patterns[before + 1..], &args[rest_end..], env, class_env);
+        }
+        None => {
+            for (pattern, arg) in patterns.iter().zip(args) {
+                set_env_from_pattern(pattern, arg, env, class_env);
+            }
+        }
+    }
+}
+
+// `{^step: 1;}` is keyed by the symbol bound to `step`
+fn prop_key(name: &str, env: &HashMap<String, Object>) -> String {
+    match name.strip_prefix('^') {
+        Some(var) => match env.get(var) {
+            Some(Object::Sym(id, desc)) => sym_key(id, desc),
+            _ => panic!("`^{}` has to be a Sym to be used as a key", var),
+        },
+        None => name.to_owned(),
     }
 }
 
+// the field name a Sym is stored under, `key_sym` turns it back into the Sym
+fn sym_key(id: &Uuid, desc: &Option<String>) -> String {
+    format!("^{}#{}", desc.as_deref().unwrap_or(""), id)
+}
+
+fn key_sym(key: &str) -> Option<Object> {
+    let (desc, id) = key.strip_prefix('^')?.rsplit_once('#')?;
+    let id = Uuid::parse_str(id).ok()?;
+    Some(Object::Sym(id, (!desc.is_empty()).then(|| desc.to_owned())))
+}
+
+// public keys, in declared order if there is one, a class answers its declared fields
+fn fields_of(object: &Object, class_env: &HashMap<Uuid, Class>) -> Vec<Object> {
+    match object {
+        Object::Instance(class_id, props) => match &class_env.get(class_id).unwrap().fields {
+            Some(fields) => fields.iter().collect(),
+            None => props.iter().map(|(name, _)| name).collect(),
+        },
+        Object::Class(class_id) => match &class_env.get(class_id).unwrap().fields {
+            Some(fields) => fields.iter().collect(),
+            None => vec![],
+        },
+        _ => vec![],
+    }
+    .into_iter()
+    .filter(|name| !name.starts_with('_'))
+    .map(|name| key_sym(name).unwrap_or_else(|| Object::Keyword(name.to_owned())))
+    .collect()
+}
+
+// the receiver's handlers up its class chain, a class answers its own
+fn handlers_of(
+    object: &Object,
+    env: &HashMap<String, Object>,
+    class_env: &HashMap<Uuid, Class>,
+) -> Vec<Object> {
+    let mut class_id = match object {
+        Object::Class(id) => Some(*id),
+        _ => Some(get_class_id(object, env)),
+    };
+    let mut handlers = vec![];
+    while let Some(class) = class_id.and_then(|id| class_env.get(&id)) {
+        handlers.extend(class.methods.iter().filter_map(|node| match &**node {
+            Node::Def(args, guard, _, _) => Some(Object::Str(def_to_s(args, guard))),
+            _ => None,
+        }));
+        class_id = class.superclass;
+    }
+    handlers
+}
+
+// fields starting with `_` are private, only `self :_name` reads them
 fn try_eval_property_lookup(
     object_properties: &HashMap<String, Object>,
     args: &[Object],
 ) -> Option<Object> {
     match args {
-        [Object::Keyword(name)] => object_properties.get(name).map(|t| t.clone()),
+        [Object::Keyword(name)] if !name.starts_with('_') => object_properties.get(name).cloned(),
+        [Object::Sym(id, desc)] => object_properties.get(&sym_key(id, desc)).cloned(),
         _ => None,
     }
 }
 
 fn find_method_for(
     class_id: Uuid,
+    receiver: &Object,
     args: &[Object],
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
-) -> Option<(Vec<Node>, Box<Node>)> {
-    match class_env.get(&class_id) {
-        Some(class) => class
-            .methods
-            .iter()
-            .filter_map(|t| match t {
-                Node::Def(args, body) => Some((args, body)),
-                _ => None,
-            })
-            .find(|(patterns, _)| match_arg_list(patterns, args, env, &mut class_env.clone()))
-            .map(|(a, b)| (a.clone(), b.clone())),
-        _ => None,
+) -> Option<Rc<Node>> {
+    let class = class_env.get(&class_id)?;
+    let mut handlers: Vec<Rc<Node>> = class
+        .methods
+        .iter()
+        .filter(|method| matches!(***method, Node::Def(..)))
+        .cloned()
+        .collect();
+    // most sends go to classes without handlers, like `1 + 2`
+    if handlers.is_empty() {
+        return None;
     }
-}
-
-fn at_most_one_spread_arg(args: &Vec<Node>) -> bool {
-    args.iter().filter(|n| matches!(n, Node::Spread(_))).count() <= 1
-}
-
-fn spread_arg_is_id_lookup_if_exists(args: &Vec<Node>) -> bool {
-    if let Some(Node::Spread(node)) = args.iter().find(|n| matches!(n, Node::Spread(_))) {
-        matches!((*node).as_ref(), Node::IdLookup(_))
-    } else {
-        true
+    if class.dispatch == Dispatch::Specificity {
+        // the sort is stable, so ties go to the handler defined first
+        handlers.sort_by_cached_key(|def| match &**def {
+            Node::Def(patterns, guard, _, _) => Reverse(specificity(patterns, guard)),
+            _ => unreachable!(),
+        });
     }
-}
 
-fn get_spread_arg_name(args: &Vec<Node>) -> String {
-    args.iter()
-        .find_map(|n| match n {
-            Node::Spread(node) => match *node.to_owned() {
-                Node::IdLookup(name) => Some(name.to_owned()),
-                _ => None,
-            },
-            _ => None,
-        })
-        .unwrap()
+    // patterns like `^(self :rhs;)` are evaluated against the receiver, `self` is
+    // put back afterwards rather than copying `env`
+    let outer_self = env.insert("self".to_owned(), receiver.clone());
+    let found = handlers.into_iter().find(|def| {
+        let Node::Def(patterns, guard, _, _) = &**def else {
+            return false;
+        };
+        if !match_arg_list(patterns, args, env, class_env) {
+            return false;
+        }
+        match guard {
+            // def n when n > 10 -> ...
+            Some(guard) => {
+                let mut guard_env = env.clone();
+                set_env_from_patterns(patterns, args, &mut guard_env, class_env);
+                is_truthy(&eval_node(guard, &mut guard_env, class_env), env)
+            }
+            None => true,
+        }
+    });
+    match outer_self {
+        Some(outer_self) => env.insert("self".to_owned(), outer_self),
+        None => env.remove("self"),
+    };
+    found
 }
 
-fn set_env_for_spread_arg(
-    method_args: &Vec<Node>,
-    args: &[Object],
-    local_env: &mut HashMap<String, Object>,
-) {
-    // 1: only 1 spread arg allowed && it should be an id lookup
-    assert!(at_most_one_spread_arg(&method_args));
-    assert!(spread_arg_is_id_lookup_if_exists(&method_args));
-
-    // 2: get arguments before spread
-    let before_spread: Vec<&Node> = method_args
-        .iter()
-        .take_while(|n| !matches!(n, Node::Spread(_)))
-        .collect();
-    // 3: get arguments after spread
-    let after_spread: Vec<&Node> = method_args
-        .iter()
-        .rev()
-        .take_while(|n| !matches!(n, Node::Spread(_)))
-        .collect::<Vec<&Node>>()
-        .iter()
-        .rev()
-        .map(|n| *n)
-        .collect();
-    // 4: set args from before spread
-    for (pattern, arg) in before_spread.iter().zip(args) {
-        set_env_from_pattern(pattern, arg, local_env)
-    }
-    // 5: determine how many spread arguments
-    let num_spread_args = args.len() - (after_spread.len() + before_spread.len());
-    // 6: assign those spread arguments
-    let spread_arg_name = get_spread_arg_name(&method_args);
-    let mut spread_elements: Vec<Object> = vec![];
-    for arg in args.iter().skip(before_spread.len()).take(num_spread_args) {
-        spread_elements.push(arg.clone());
-    }
-    local_env.insert(spread_arg_name, Object::List(spread_elements));
-    // 7: set args after the spread
-    for (pattern, arg) in after_spread
-        .iter()
-        .zip(args.iter().skip(after_spread.len() + num_spread_args))
-    {
-        set_env_from_pattern(pattern, arg, local_env);
-    }
+fn at_most_one_spread_arg(args: &[Node]) -> bool {
+    args.iter().filter(|n| matches!(n, Node::Spread(_))).count() <= 1
 }
 
+// runs the first matching handler up the class chain, `None` if nothing matches
 fn method_call(
     class_id: Uuid,
-    object_properties: HashMap<String, Object>,
+    receiver: &Object,
     args: &[Object],
     env: &mut HashMap<String, Object>,
     class_env: &mut HashMap<Uuid, Class>,
-) -> Object {
-    // Person{name: "marcelle";} :name;
-    if let Some(val) = try_eval_property_lookup(&object_properties, args) {
-        return val;
-    }
-
-    match find_method_for(class_id, args, env, class_env) {
-        Some((method_args, body)) => {
-            let mut local_env: HashMap<String, Object> = HashMap::new();
-            if method_args.iter().any(|n| matches!(n, Node::Spread(_))) {
-                set_env_for_spread_arg(&method_args, args, &mut local_env);
-            } else {
-                set_env_from_patterns(&method_args, args, env);
-            }
-            local_env.insert(
-                "self".to_string(),
-                Object::Instance(
-                    class_id,
-                    object_properties
-                        .iter()
-                        .map(|(a, b)| (a.clone(), b.clone()))
-                        .collect(),
-                ),
-            );
-            for (key, val) in env {
-                if let Some(_) = local_env.get(key) {
-                    continue;
-                }
-                local_env.insert(key.to_owned(), val.clone());
-            }
-            eval_node(&body, &mut local_env, &mut class_env.clone())
+) -> Option<Object> {
+    match find_method_for(class_id, receiver, args, env, class_env) {
+        Some(def) => {
+            let Node::Def(patterns, _, body, _) = &*def else {
+                unreachable!()
+            };
+            let mut local_env = env.clone();
+            local_env.insert("self".to_string(), receiver.clone());
+            set_env_from_patterns(patterns, args, &mut local_env, class_env);
+            Some(eval_node(body, &mut local_env, class_env))
         }
         None => {
             if let Some(Class {
-                name: _,
-                methods: _,
                 superclass: Some(superclass_id),
+                ..
             }) = class_env.get(&class_id)
             {
-                method_call(*superclass_id, object_properties, args, env, class_env)
+                method_call(*superclass_id, receiver, args, env, class_env)
             } else {
-                panic!("no method found");
+                None
             }
         }
     }
@@ -643,12 +2143,125 @@ fn method_call(
 
 fn get_object_class_id(env: &HashMap<String, Object>) -> Option<Uuid> {
     if let Some(Object::Class(id)) = env.get("Object") {
-        Some(id.clone())
+        Some(*id)
     } else {
         panic!("no object class")
     }
 }
 
+// classes dispatch the same way as `Object`
+fn get_object_dispatch(
+    env: &HashMap<String, Object>,
+    class_env: &HashMap<Uuid, Class>,
+) -> Dispatch {
+    get_object_class_id(env)
+        .and_then(|id| class_env.get(&id))
+        .map(|class| class.dispatch)
+        .unwrap_or(Dispatch::Declaration)
+}
+
+// the elements of `Name[a; b;]` in order, `None` if the instance isn't positional
+fn positional_values(
+    class_id: &Uuid,
+    props: &[(String, Object)],
+    class_env: &HashMap<Uuid, Class>,
+) -> Option<Vec<Object>> {
+    match &class_env.get(class_id)?.fields {
+        Some(fields) => fields
+            .iter()
+            .map(|field| {
+                props
+                    .iter()
+                    .find(|(name, _)| name == field)
+                    .map(|(_, val)| val.clone())
+            })
+            .collect(),
+        None => props
+            .iter()
+            .enumerate()
+            .map(|(idx, (name, val))| (*name == idx.to_string()).then(|| val.clone()))
+            .collect(),
+    }
+}
+
+// instances keep their properties sorted, a symbol key sorts by its id
+fn eval_properties(
+    properties: &[(String, Node)],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Vec<(String, Object)> {
+    let mut props: Vec<(String, Object)> = properties
+        .iter()
+        .map(|(name, node)| (prop_key(name, env), eval_node(node, env, class_env)))
+        .collect();
+    props.sort_by(|a, b| a.0.cmp(&b.0));
+    props
+}
+
+// a constructor for a class with declared fields has to give exactly those fields
+fn check_fields(constructor: &Node, class: &Class, properties: &[(String, Node)]) {
+    let Some(fields) = &class.fields else {
+        return;
+    };
+    for (name, _) in properties {
+        if !fields.contains(name) {
+            panic!(
+                "`{}`: {} has no field `{}`, its fields are {}",
+                pattern_to_s(constructor),
+                class.name,
+                name,
+                fields.join(", ")
+            );
+        }
+    }
+    for field in fields {
+        if !properties.iter().any(|(name, _)| name == field) {
+            panic!(
+                "`{}`: {} is missing the field `{}`",
+                pattern_to_s(constructor),
+                class.name,
+                field
+            );
+        }
+    }
+}
+
+fn get_or_define_class(
+    name: &String,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Uuid {
+    if let Some(Object::Class(id)) = env.get(name) {
+        return *id;
+    }
+
+    let uuid = Uuid::new_v4();
+    env.insert(name.to_owned(), Object::Class(uuid));
+    class_env.insert(
+        uuid,
+        Class {
+            name: name.to_owned(),
+            methods: vec![],
+            superclass: get_object_class_id(env),
+            fields: None,
+            dispatch: get_object_dispatch(env, class_env),
+        },
+    );
+    uuid
+}
+
+// `Bool{}` matches instances of the classes in the Bool family too
+fn is_instance_of(class_id: &Uuid, name: &String, class_env: &HashMap<Uuid, Class>) -> bool {
+    match class_env.get(class_id) {
+        Some(class) if &class.name == name => true,
+        Some(Class {
+            superclass: Some(superclass_id),
+            ..
+        }) => is_instance_of(superclass_id, name, class_env),
+        _ => false,
+    }
+}
+
 fn get_class_id(object: &Object, env: &HashMap<String, Object>) -> Uuid {
     match object {
         Object::Instance(class_id, _) => *class_id,
@@ -679,36 +2292,261 @@ fn get_class_id(object: &Object, env: &HashMap<String, Object>) -> Uuid {
             } else {
                 panic!("Couldn't find Int class")
             }
-        }
-        Object::Class(_) => todo!(),
-        Object::Operator(_) => {
-            if let Some(Object::Class(id)) = env.get("Operator") {
-                *id
-            } else {
-                panic!("Couldn't find Operator class")
+        }
+        Object::Class(_) => {
+            if let Some(Object::Class(id)) = env.get("Class") {
+                *id
+            } else {
+                panic!("Couldn't find Class class")
+            }
+        }
+        Object::Cell(_) => {
+            if let Some(Object::Class(id)) = env.get("Cell") {
+                *id
+            } else {
+                panic!("Couldn't find Cell class")
+            }
+        }
+        Object::Range(..) => {
+            if let Some(Object::Class(id)) = env.get("Range") {
+                *id
+            } else {
+                panic!("Couldn't find Range class")
+            }
+        }
+        Object::Iter(_) => {
+            if let Some(Object::Class(id)) = env.get("Iter") {
+                *id
+            } else {
+                panic!("Couldn't find Iter class")
+            }
+        }
+        Object::Placeholder(_) => {
+            if let Some(Object::Class(id)) = env.get("Underscore") {
+                *id
+            } else {
+                panic!("Couldn't find Underscore class")
+            }
+        }
+        Object::Dict(_) => {
+            if let Some(Object::Class(id)) = env.get("Dict") {
+                *id
+            } else {
+                panic!("Couldn't find Dict class")
+            }
+        }
+        Object::Set(_) => {
+            if let Some(Object::Class(id)) = env.get("Set") {
+                *id
+            } else {
+                panic!("Couldn't find Set class")
+            }
+        }
+        Object::Sym(..) => {
+            if let Some(Object::Class(id)) = env.get("Sym") {
+                *id
+            } else {
+                panic!("Couldn't find Sym class")
+            }
+        }
+        Object::Operator(_) => {
+            if let Some(Object::Class(id)) = env.get("Operator") {
+                *id
+            } else {
+                panic!("Couldn't find Operator class")
+            }
+        }
+        Object::List(_) => {
+            if let Some(Object::Class(id)) = env.get("List") {
+                *id
+            } else {
+                panic!("Couldn't find List class")
+            }
+        }
+    }
+}
+
+fn get_object_properties(object: &Object) -> HashMap<String, Object> {
+    let mut properties: HashMap<String, Object> = HashMap::new();
+    if let Object::Instance(_, props) = object {
+        for (key, val) in props {
+            properties.insert(key.clone(), val.clone());
+        }
+    }
+    properties
+}
+
+// `:map f g` sends each item to `f`, then the result to `g`
+fn send_through(
+    fs: &[Object],
+    item: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    fs.iter()
+        .fold(item.clone(), |val, f| call(f, &val, env, class_env))
+}
+
+// how `:map` & co send an item to an object, a placeholder is applied to the
+// item even when it's a keyword
+fn call(
+    f: &Object,
+    arg: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    match f {
+        Object::Placeholder(messages) if !messages.is_empty() => {
+            replay(messages, arg, env, class_env)
+        }
+        _ => send_message(f, std::slice::from_ref(arg), env, class_env),
+    }
+}
+
+fn replay(
+    messages: &[Vec<Object>],
+    arg: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    messages.iter().fold(arg.clone(), |val, message| {
+        send_message(&val, message, env, class_env)
+    })
+}
+
+fn has_placeholders(object: &Object) -> bool {
+    match object {
+        Object::Instance(_, props) => props
+            .iter()
+            .any(|(_, val)| matches!(val, Object::Placeholder(_))),
+        _ => false,
+    }
+}
+
+// `Classify{fizz: _ % 3;}` sent 9 is `Classify{fizz: 0;}`
+fn fill_placeholders(
+    receiver: &Object,
+    arg: &Object,
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    match receiver {
+        Object::Instance(class_id, props) => Object::Instance(
+            *class_id,
+            props
+                .iter()
+                .map(|(name, val)| match val {
+                    Object::Placeholder(messages) => {
+                        (name.to_owned(), replay(messages, arg, env, class_env))
+                    }
+                    _ => (name.to_owned(), val.clone()),
+                })
+                .collect(),
+        ),
+        _ => receiver.clone(),
+    }
+}
+
+fn send_message(
+    lhs: &Object,
+    args: &[Object],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Object {
+    // `_` records messages until it has some & is sent a single argument that isn't a
+    // keyword, so `_ :trim. :upcase` is one chain & `:call` applies it to a keyword.
+    // `:log` & `:inspect` aren't recorded, they show the chain
+    if let Object::Placeholder(messages) = lhs {
+        match args {
+            [Object::Keyword(name)] if name == "log" || name == "inspect" => (),
+            [Object::Keyword(name), arg] if name == "call" && !messages.is_empty() => {
+                return replay(messages, arg, env, class_env)
+            }
+            [arg] if !messages.is_empty() && !matches!(arg, Object::Keyword(_)) => {
+                return replay(messages, arg, env, class_env)
             }
-        }
-        Object::List(_) => {
-            if let Some(Object::Class(id)) = env.get("List") {
-                *id
-            } else {
-                panic!("Couldn't find List class")
+            _ => {
+                let mut messages = messages.to_vec();
+                messages.push(args.to_vec());
+                return Object::Placeholder(messages);
             }
         }
     }
+
+    // `[1; 2;] :native :map f` skips any handlers defined on List
+    if let [Object::Keyword(name), native_args @ ..] = args {
+        if name == "native" {
+            return try_eval_native_fn(lhs, native_args, env, class_env)
+                .or_else(|| try_eval_default_fn(lhs, native_args, env, class_env))
+                .unwrap_or_else(|| {
+                    panic!("{} has no native method for that", lhs.to_s(class_env))
+                });
+        }
+    }
+
+    // a wildcard handler like `def n` doesn't swallow `:log`, `==` & co, a `def :log` does
+    let selector = match args.first() {
+        Some(Object::Keyword(name) | Object::Operator(name)) => name.as_str(),
+        _ => "",
+    };
+    let universal = UNIVERSAL_MESSAGES.iter().any(|(name, _)| *name == selector);
+    if universal && !defines_selector(lhs, args, env, class_env) {
+        if let Some(val) = try_eval_universal_fn(lhs, args, env, class_env)
+            .or_else(|| try_eval_default_fn(lhs, args, env, class_env))
+        {
+            return val;
+        }
+    }
+
+    // Person{name: "marcelle";} :name; the field only gives way to a `def :name`
+    // or to a native, a wildcard handler like `def n` doesn't shadow it. Handlers
+    // aren't matched to decide, `def ^(self :max;)` would ask for the field again
+    let field = try_eval_property_lookup(&get_object_properties(lhs), args);
+    if let (Some(val), [Object::Keyword(name)]) = (&field, args) {
+        if !has_handler_named(lhs, name, env, class_env) {
+            return try_eval_native_fn(lhs, args, env, class_env).unwrap_or(val.clone());
+        }
+    }
+
+    // handlers defined on Int, List etc. come before the natives
+    if let Some(val) = method_call(get_class_id(lhs, env), lhs, args, env, class_env) {
+        return val;
+    }
+
+    // is it a native function?
+    if let Some(val) = try_eval_native_fn(lhs, args, env, class_env) {
+        return val;
+    }
+
+    match (try_eval_default_fn(lhs, args, env, class_env), args) {
+        (Some(val), _) => val,
+        // a `def :name when ...` whose guard fails
+        (None, _) if field.is_some() => field.unwrap(),
+        (None, [Object::Keyword(name)]) if get_object_properties(lhs).contains_key(name) => {
+            panic!("`{}` is private to {}", name, lhs.to_s(class_env))
+        }
+        (None, _) => panic!("no method found"),
+    }
 }
 
-fn get_object_properties(object: &Object) -> HashMap<String, Object> {
-    let mut properties: HashMap<String, Object> = HashMap::new();
-    match object {
-        Object::Instance(_, props) => {
-            for (key, val) in props {
-                properties.insert(key.clone(), val.clone());
+// evaluates list elements or arguments, splicing in `...list`
+fn eval_elements(
+    nodes: &[Node],
+    env: &mut HashMap<String, Object>,
+    class_env: &mut HashMap<Uuid, Class>,
+) -> Vec<Object> {
+    let mut objects: Vec<Object> = vec![];
+    for node in nodes {
+        if let Node::Spread(node) = node {
+            match eval_node(node, env, class_env) {
+                Object::List(items) => objects.extend(items),
+                other => panic!("can't spread {}", other.to_s(class_env)),
             }
+        } else {
+            objects.push(eval_node(node, env, class_env));
         }
-        _ => (),
     }
-    properties
+    objects
 }
 
 fn eval_node(
@@ -718,43 +2556,58 @@ fn eval_node(
 ) -> Object {
     match node {
         Node::MethodCall(lhs, args) => {
-            let arg_objects: Vec<Object> = args
-                .iter()
-                .map(|item| eval_node(item, env, class_env))
-                .collect();
+            let arg_objects = eval_elements(args, env, class_env);
             let lhs_object = &eval_node(lhs.as_ref(), env, class_env);
 
-            // is it a native function?
-            if let Some(val) = try_eval_native_fn(lhs_object, &arg_objects, env, class_env) {
-                return val;
+            // inside a handler `self :_secret` reads a private field
+            if let (Node::IdLookup(id), [Object::Keyword(name)]) =
+                (lhs.as_ref(), arg_objects.as_slice())
+            {
+                if id == "self" && name.starts_with('_') {
+                    if let Some(val) = get_object_properties(lhs_object).get(name) {
+                        return val.clone();
+                    }
+                }
             }
 
-            method_call(
-                get_class_id(lhs_object, env),
-                get_object_properties(lhs_object),
-                &arg_objects,
-                env,
-                class_env,
-            )
+            send_message(lhs_object, &arg_objects, env, class_env)
         }
         Node::Keyword(name) => Object::Keyword(name.to_owned()),
-        Node::Class(name, defs) => {
-            let uuid: Uuid;
+        Node::Class(name, members, fields, defs) => {
+            let uuid = get_or_define_class(name, env, class_env);
 
-            if let Some(Object::Class(id)) = env.get(name) {
-                uuid = *id;
-            } else {
-                uuid = Uuid::new_v4();
-                env.insert(name.to_owned(), Object::Class(uuid));
-
-                class_env.insert(
-                    uuid,
-                    Class {
-                        name: name.to_owned(),
-                        methods: vec![],
-                        superclass: get_object_class_id(env),
-                    },
-                );
+            if let Some(fields) = fields {
+                let class = class_env.get_mut(&uuid).unwrap();
+                match &class.fields {
+                    Some(declared) if declared != fields => {
+                        panic!("class {} already declares its fields", name)
+                    }
+                    _ => class.fields = Some(fields.to_vec()),
+                }
+            }
+
+            // class Bool = TrueClass | FalseClass; a family is closed once it's declared
+            if !members.is_empty() {
+                let object_id = get_object_class_id(env);
+                if class_env
+                    .values()
+                    .any(|class| class.superclass == Some(uuid))
+                {
+                    panic!("the family {} is already declared", name)
+                }
+                for member in members {
+                    let member_id = get_or_define_class(member, env, class_env);
+                    let class = class_env.get_mut(&member_id).unwrap();
+                    if class.superclass != object_id {
+                        let family = class.superclass.and_then(|id| class_env.get(&id));
+                        panic!(
+                            "{} is already in the family {}",
+                            member,
+                            family.map_or("?", |family| family.name.as_str())
+                        )
+                    }
+                    class.superclass = Some(uuid);
+                }
             }
 
             let mut child_env = env.clone();
@@ -769,18 +2622,42 @@ fn eval_node(
         }
         Node::RecordConstructor(name, properties) => {
             if let Some(Object::Class(uuid)) = env.get(name) {
-                Object::Instance(
-                    *uuid,
-                    properties
-                        .iter()
-                        .map(|(name, node)| (name.to_owned(), eval_node(node, env, class_env)))
-                        .collect(),
-                )
+                let uuid = *uuid;
+                check_fields(node, class_env.get(&uuid).unwrap(), properties);
+                Object::Instance(uuid, eval_properties(properties, env, class_env))
             } else {
                 todo!("Class unknown")
             }
         }
-        Node::Int(val) => Object::Int(*val),
+        Node::RecordUpdate(name, base, properties) => {
+            let (class_id, mut props) = match eval_node(base, env, class_env) {
+                Object::Instance(class_id, props) if is_instance_of(&class_id, name, class_env) => {
+                    (class_id, props)
+                }
+                other => panic!("can't copy {} as a {}", other.to_s(class_env), name),
+            };
+            for (prop, value) in properties {
+                let val = eval_node(value, env, class_env);
+                let prop = prop_key(prop, env);
+                match props.iter_mut().find(|(name, _)| *name == prop) {
+                    Some((_, old)) => *old = val,
+                    // a class without declared fields takes new ones, like its constructor
+                    None => match &class_env.get(&class_id).unwrap().fields {
+                        Some(fields) => panic!(
+                            "`{}`: {} has no field `{}`, its fields are {}",
+                            pattern_to_s(node),
+                            class_env.get(&class_id).unwrap().name,
+                            prop,
+                            fields.join(", ")
+                        ),
+                        None => props.push((prop, val)),
+                    },
+                }
+            }
+            // the copy keeps the class of the original, `Shape{...circle;}` is still a Circle
+            Object::Instance(class_id, props)
+        }
+        Node::Int(val) => Object::Int(*val as i64),
         Node::IdLookup(name) => {
             if let Some(val) = env.get(name) {
                 val.to_owned()
@@ -797,18 +2674,11 @@ fn eval_node(
             Object::Nil
         }
         Node::Operator(name) => Object::Operator(name.to_owned()),
-        Node::List(items) => Object::List(
-            items
-                .iter()
-                .map(|item| eval_node(item, env, class_env))
-                .collect(),
-        ),
-        Node::Def(args, body) => {
+        Node::List(items) => Object::List(eval_elements(items, env, class_env)),
+        Node::Def(_, _, _, _) => {
             if let Some(Object::Instance(id, _)) = env.get("self") {
                 let class = class_env.get_mut(id).unwrap();
-                class
-                    .methods
-                    .push(Node::Def(args.to_owned(), body.to_owned()));
+                class.methods.push(Rc::new(node.to_owned()));
                 Object::Nil
             } else {
                 panic!("No self")
@@ -816,38 +2686,90 @@ fn eval_node(
         }
         Node::Str(val) => Object::Str(val.to_owned()),
         Node::VectorConstructor(name, exprs) => {
-            assert!(exprs.len() == 1);
-            let expr = eval_node(exprs.first().unwrap(), env, class_env);
-            if let Some(Object::Class(id)) = env.get(name) {
-                Object::Instance(*id, vec![("value".to_owned(), expr)])
-            } else {
+            let objs = eval_elements(exprs, env, class_env);
+            if name == "Cell" {
+                assert!(objs.len() == 1, "`{}` needs one value", pattern_to_s(node));
+                return Object::Cell(Ref::new(objs.first().unwrap().clone()));
+            }
+            if name == "Set" {
+                return set_from(objs, env, class_env);
+            }
+            // Sym[:step;] is a new symbol every time
+            if name == "Sym" {
+                return match objs.as_slice() {
+                    [] => Object::Sym(Uuid::new_v4(), None),
+                    [Object::Keyword(desc)] => Object::Sym(Uuid::new_v4(), Some(desc.to_owned())),
+                    _ => panic!(
+                        "`{}`: a Sym is described by one keyword",
+                        pattern_to_s(node)
+                    ),
+                };
+            }
+            let Some(Object::Class(id)) = env.get(name) else {
                 panic!("no class found {}", name)
+            };
+            let id = *id;
+            match &class_env.get(&id).unwrap().fields {
+                // class Point{x; y;}, Point[1; 2;] is Point{x: 1; y: 2;}
+                Some(fields) => {
+                    if fields.len() != objs.len() {
+                        panic!(
+                            "`{}`: {} has {} fields, {} given",
+                            pattern_to_s(node),
+                            name,
+                            fields.len(),
+                            objs.len()
+                        );
+                    }
+                    let mut props: Vec<(String, Object)> =
+                        fields.iter().cloned().zip(objs).collect();
+                    props.sort_by(|a, b| a.0.cmp(&b.0));
+                    Object::Instance(id, props)
+                }
+                None => Object::Instance(
+                    id,
+                    objs.into_iter()
+                        .enumerate()
+                        .map(|(idx, obj)| (idx.to_string(), obj))
+                        .collect(),
+                ),
             }
         }
-        Node::Unquote(_) => todo!(),
+        // outside of a pattern there is nothing to unquote from
+        Node::Unquote(node) => eval_node(node, env, class_env),
         Node::ParenExpr(node) => eval_node(node, env, class_env),
-        Node::Spread(_) => todo!("ah"),
+        Node::Spread(_) => panic!("`...` can only be used in a list or in arguments"),
+        Node::Or(_) | Node::As(_, _) => panic!("patterns can't be evaluated"),
         Node::Object(methods) => {
             let id = Uuid::new_v4();
             class_env.insert(
                 id,
                 Class {
                     name: "<anon class>".to_string(),
-                    methods: methods.to_vec(),
+                    methods: methods.iter().cloned().map(Rc::new).collect(),
                     superclass: get_object_class_id(env),
+                    fields: None,
+                    dispatch: get_object_dispatch(env, class_env),
                 },
             );
             Object::Instance(id, vec![])
         }
+        Node::Dict(entries) => {
+            let entries: Vec<(Object, Object)> = entries
+                .iter()
+                .map(|(key, val)| {
+                    (
+                        eval_node(key, env, class_env),
+                        eval_node(val, env, class_env),
+                    )
+                })
+                .collect();
+            dict_from(entries, env, class_env)
+        }
         Node::RecordLiteral(properties) => {
             if let Object::Class(id) = env.get("Object").unwrap() {
-                Object::Instance(
-                    *id,
-                    properties
-                        .iter()
-                        .map(|(a, b)| (a.to_owned(), eval_node(b, env, class_env)))
-                        .collect(),
-                )
+                let id = *id;
+                Object::Instance(id, eval_properties(properties, env, class_env))
             } else {
                 panic!("oh no")
             }
@@ -855,12 +2777,13 @@ fn eval_node(
     }
 }
 
-pub fn interpret(ast: Vec<Node>) -> Object {
+pub fn interpret(ast: Vec<Node>, dispatch: Dispatch) -> Object {
     let main_id = Uuid::new_v4();
     let object_id = Uuid::new_v4();
     let mut env: HashMap<String, Object> = HashMap::from([
         ("self".to_owned(), Object::Instance(main_id, vec![])),
         ("Object".to_owned(), Object::Class(object_id)),
+        ("_".to_owned(), Object::Placeholder(vec![])),
     ]);
     let mut class_env: HashMap<Uuid, Class> = HashMap::from([
         (
@@ -869,6 +2792,8 @@ pub fn interpret(ast: Vec<Node>) -> Object {
                 name: "Main".to_string(),
                 methods: vec![],
                 superclass: None,
+                fields: None,
+                dispatch,
             },
         ),
         (
@@ -877,6 +2802,8 @@ pub fn interpret(ast: Vec<Node>) -> Object {
                 name: "Object".to_string(),
                 methods: vec![],
                 superclass: None,
+                fields: None,
+                dispatch,
             },
         ),
     ]);
@@ -887,3 +2814,1191 @@ pub fn interpret(ast: Vec<Node>) -> Object {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::Parser;
+
+    // runs `setup` and then `expr` after the prelude, giving the value of `expr`
+    fn eval_after_with(setup: &str, expr: &str, dispatch: Dispatch) -> Object {
+        let program = format!("{}{}\n{}", include_str!("prelude.pnt"), setup, expr);
+        interpret(
+            Parser {
+                tokens: tokenize(program),
+                idx: 0,
+            }
+            .parse(),
+            dispatch,
+        )
+    }
+
+    fn eval_after(setup: &str, expr: &str) -> Object {
+        eval_after_with(setup, expr, Dispatch::Declaration)
+    }
+
+    fn eval(program: &str) -> Object {
+        eval_after("", program)
+    }
+
+    #[test]
+    fn an_operator_sent_two_arguments_applies_itself() {
+        let program = "plus := +;";
+        assert_eq!(eval("+ :call 1 2;"), Object::Int(3));
+        assert_eq!(eval_after(program, "plus 1 2;"), Object::Int(3));
+        assert_eq!(
+            eval_after(program, "[1; 2; 3;] :reduce plus;"),
+            Object::Int(6)
+        );
+    }
+
+    fn str(val: &str) -> Object {
+        Object::Str(val.to_owned())
+    }
+
+    fn keyword(name: &str) -> Object {
+        Object::Keyword(name.to_owned())
+    }
+
+    #[test]
+    fn unquote_pins_a_name_bound_earlier_in_the_pattern() {
+        let program = "
+            class Pair
+              def :same? a ^a -> :same;
+              def :same? _ _ -> :different;
+            end
+        ";
+        assert_eq!(eval_after(program, "Pair{} :same? 1 1;"), keyword("same"));
+        assert_eq!(
+            eval_after(program, "Pair{} :same? 1 2;"),
+            keyword("different")
+        );
+        assert_eq!(
+            eval_after(program, "Pair{} :same? [1; 2;] [1; 2;];"),
+            keyword("same")
+        );
+    }
+
+    #[test]
+    fn unquote_in_a_pattern_can_read_the_receiver() {
+        let program = "
+            class Box
+              def :has? ^(self :value;) -> :yes;
+              def :has? _ -> :no;
+            end
+        ";
+        assert_eq!(
+            eval_after(program, "Box{value: 3;} :has? 3;"),
+            keyword("yes")
+        );
+        assert_eq!(
+            eval_after(program, "Box{value: 3;} :has? 4;"),
+            keyword("no")
+        );
+    }
+
+    #[test]
+    fn unquote_pins_a_variable_from_outside() {
+        let program = "
+            limit := 10;
+            at_limit := object
+              def ^limit -> :at;
+              def _ -> :not_at;
+            end;
+        ";
+        assert_eq!(eval_after(program, "at_limit 10;"), keyword("at"));
+        assert_eq!(eval_after(program, "at_limit 9;"), keyword("not_at"));
+    }
+
+    #[test]
+    fn unquote_as_an_expression_is_the_value() {
+        assert_eq!(eval("x := 2; ^(x + 1;);"), Object::Int(3));
+        assert_eq!(eval("^\"a\";"), str("a"));
+    }
+
+    #[test]
+    fn ints_are_signed_and_have_arithmetic() {
+        assert_eq!(eval("2 - 5;"), Object::Int(-3));
+        assert_eq!(eval("2 - 5. * 4;"), Object::Int(-12));
+        assert_eq!(eval("2 ** 10;"), Object::Int(1024));
+        // `/` & `%` round towards negative infinity
+        assert_eq!(eval("0 - 7. / 2;"), Object::Int(-4));
+        assert_eq!(eval("0 - 7. % 2;"), Object::Int(1));
+    }
+
+    #[test]
+    fn comparisons_give_booleans() {
+        let program = "
+            truth := object
+              def ^true -> :yes;
+              def ^false -> :no;
+            end;
+        ";
+        let yes_no =
+            |answers: &[&str]| Object::List(answers.iter().map(|name| keyword(name)).collect());
+        assert_eq!(
+            eval_after(
+                program,
+                "[1 >= 2; 1 <= 2; 1 == 1; 1 > 1; 1 < 2;] :map truth;"
+            ),
+            yes_no(&["no", "yes", "yes", "no", "yes"])
+        );
+    }
+
+    #[test]
+    fn a_guard_picks_the_first_handler_whose_guard_holds() {
+        let program = "
+            size := object
+              def n when n > 100 -> :huge;
+              def n when n >= 10 -> :big;
+              def _ -> :small;
+            end;
+        ";
+        assert_eq!(eval_after(program, "size 101;"), keyword("huge"));
+        assert_eq!(eval_after(program, "size 10;"), keyword("big"));
+        assert_eq!(eval_after(program, "size 5;"), keyword("small"));
+    }
+
+    #[test]
+    fn a_guard_sees_names_bound_by_the_patterns() {
+        let program = "
+            order := object
+              def a b when a < b -> :ascending;
+              def _ _ -> :other;
+            end;
+        ";
+        assert_eq!(eval_after(program, "order 1 2;"), keyword("ascending"));
+        assert_eq!(eval_after(program, "order 2 1;"), keyword("other"));
+    }
+
+    #[test]
+    fn an_or_pattern_matches_any_alternative() {
+        let program = "
+            kind := object
+              def 1 | 2 -> :small;
+              def ^true | ^false -> :bool;
+              def _ -> :other;
+            end;
+        ";
+        assert_eq!(eval_after(program, "kind 2;"), keyword("small"));
+        assert_eq!(eval_after(program, "kind false;"), keyword("bool"));
+        assert_eq!(eval_after(program, "kind 3;"), keyword("other"));
+    }
+
+    #[test]
+    fn an_as_pattern_binds_the_whole_value() {
+        let program = "
+            class Point
+              def :swap p @ Point{x; y;} -> [p :x; x; y;];
+            end
+            plus_ten := object
+              def n @ 3 | 4 -> n + 10;
+            end;
+        ";
+        assert_eq!(
+            eval_after(program, "Point{} :swap Point{x: 1; y: 2;};"),
+            Object::List(vec![Object::Int(1), Object::Int(1), Object::Int(2)])
+        );
+        assert_eq!(eval_after(program, "plus_ten 4;"), Object::Int(14));
+    }
+
+    fn ints(vals: &[i64]) -> Object {
+        Object::List(vals.iter().map(|val| Object::Int(*val)).collect())
+    }
+
+    #[test]
+    fn a_rest_pattern_recurses_over_a_list() {
+        let program = "
+            class Sum
+              def [] -> 0;
+              def [head; ...tail;] -> head + (self tail;);
+            end
+        ";
+        assert_eq!(eval_after(program, "Sum{} [1; 2; 3;];"), Object::Int(6));
+        assert_eq!(eval_after(program, "Sum{} [];"), Object::Int(0));
+    }
+
+    #[test]
+    fn a_rest_pattern_can_be_anywhere_and_nested() {
+        let program = "
+            edges := object
+              def [first; ...middle; last;] -> [first; last; ...middle;];
+            end;
+            inner := object
+              def [[a; ...rest;]; _;] -> rest;
+            end;
+        ";
+        assert_eq!(
+            eval_after(program, "edges [1; 2; 3; 4;];"),
+            ints(&[1, 4, 2, 3])
+        );
+        assert_eq!(eval_after(program, "edges [1; 2;];"), ints(&[1, 2]));
+        assert_eq!(
+            eval_after(program, "inner [[1; 2; 3;]; 4;];"),
+            ints(&[2, 3])
+        );
+    }
+
+    #[test]
+    fn spread_splices_into_lists_and_arguments() {
+        assert_eq!(eval("rest := [2; 3;]; [1; ...rest;];"), ints(&[1, 2, 3]));
+        assert_eq!(eval("args := [+; 2;]; 1 ...args;"), Object::Int(3));
+    }
+
+    #[test]
+    fn specificity_dispatch_prefers_the_most_specific_handler() {
+        let program = "
+            class Two
+              def _ -> :anything;
+              def Int[n;] -> :int;
+              def 2 -> :two;
+              def [x; ...rest;] -> :list;
+              def [1; 2;] -> :one_two;
+            end
+        ";
+        let specific = |expr| eval_after_with(program, expr, Dispatch::Specificity);
+        assert_eq!(specific("Two{} 2;"), keyword("two"));
+        assert_eq!(specific("Two{} 3;"), keyword("int"));
+        assert_eq!(specific("Two{} :x;"), keyword("anything"));
+        assert_eq!(specific("Two{} [1; 2;];"), keyword("one_two"));
+        assert_eq!(specific("Two{} [1; 3;];"), keyword("list"));
+        assert_eq!(eval_after(program, "Two{} 2;"), keyword("anything"));
+    }
+
+    // `expr :to_s` after `setup`, which is how a test reads a boolean
+    fn show(setup: &str, expr: &str) -> Object {
+        eval_after(setup, &format!("({};) :to_s;", expr))
+    }
+
+    #[test]
+    fn a_to_s_handler_is_used_for_printing() {
+        let program = "
+            class P
+              def :to_s -> \"<p>\";
+            end
+        ";
+        assert_eq!(show(program, "P{x: 1;}"), str("<p>"));
+        assert_eq!(show(program, "[P{x: 1;}; 2;]"), str("[<p>; 2;]"));
+        assert_eq!(show(program, "{inner: P{x: 1;};}"), str("{inner: <p>;}"));
+        assert_eq!(show(program, "true"), str("true"));
+        // `:inspect` always shows the structure
+        assert_eq!(eval_after(program, "P{x: 1;} :inspect;"), str("P{x: 1;}"));
+    }
+
+    #[test]
+    fn equality_and_patterns_use_a_classes_own_equality() {
+        let program = "
+            class Money
+              def == Money{cents;} -> self :cents. == cents;
+              def == _ -> false;
+              def :hash -> self :cents. :hash;
+            end
+            usd := Money{cents: 5; currency: :usd;};
+            eur := Money{cents: 5; currency: :eur;};
+            same := object
+              def a ^a -> :same;
+              def _ _ -> :different;
+            end;
+        ";
+        assert_eq!(show(program, "usd == eur"), str("true"));
+        assert_eq!(
+            show(program, "usd == Money{cents: 6; currency: :usd;}"),
+            str("false")
+        );
+        assert_eq!(show(program, "usd :hash. == (eur :hash;)"), str("true"));
+        assert_eq!(eval_after(program, "same usd eur;"), keyword("same"));
+        // without a `def ==` instances compare field by field
+        assert_eq!(show(program, "{a: [1;];} == {a: [1;];}"), str("true"));
+        assert_eq!(show(program, "{a: [1;];} == {a: [2;];}"), str("false"));
+    }
+
+    #[test]
+    fn every_name_bound_to_a_cell_sees_it_change() {
+        let program = "
+            counter := Cell[0;];
+            same := counter;
+            counter :set 1;
+            counter :update
+              object
+                def n -> n + 1;
+              end;
+        ";
+        assert_eq!(eval_after(program, "same :get;"), Object::Int(2));
+        assert_eq!(show(program, "counter :identical? same"), str("true"));
+        // a cell is only equal to itself, whatever it holds
+        assert_eq!(show(program, "counter == Cell[2;]"), str("false"));
+        assert_eq!(show(program, "counter == same"), str("true"));
+    }
+
+    #[test]
+    fn a_cell_that_holds_itself_prints() {
+        let program = "
+            cell := Cell[0;];
+            cell :set [1; cell;];
+        ";
+        assert_eq!(show(program, "cell"), str("Cell[[1; Cell[...];];]"));
+    }
+
+    #[test]
+    fn a_record_update_copies_with_fields_replaced() {
+        let program = "
+            class Point;
+            class Shape = Circle | Square;
+            p := Point{x: 1; y: 1;};
+        ";
+        assert_eq!(
+            show(program, "Point{...p; x: 5;}"),
+            str("Point{x: 5; y: 1;}")
+        );
+        assert_eq!(show(program, "p"), str("Point{x: 1; y: 1;}"));
+        // the copy keeps the class of the original
+        assert_eq!(
+            show(program, "Shape{...Circle{r: 1;}; r: 2;}"),
+            str("Circle{r: 2;}")
+        );
+    }
+
+    #[test]
+    fn a_record_update_adds_fields_only_to_open_classes() {
+        assert_eq!(
+            show("class P; p := P{x: 1;};", "P{...p; z: 5;}"),
+            str("P{x: 1; z: 5;}")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`Point{...p; z: 5;}`: Point has no field `z`, its fields are x, y")]
+    fn a_record_update_cant_add_a_field_to_a_declared_class() {
+        eval("class Point{x; y;}; p := Point{x: 1; y: 2;}; Point{...p; z: 5;};");
+    }
+
+    #[test]
+    #[should_panic(expected = "can't copy Point{x: 1;} as a Circle")]
+    fn a_record_update_cant_change_the_class() {
+        eval("class Point; class Circle; Circle{...Point{x: 1;}; r: 2;};");
+    }
+
+    #[test]
+    fn a_class_with_declared_fields_builds_from_them() {
+        let program = "class User{name; age;};";
+        assert_eq!(
+            show(program, "User{age: 30; name: \"al\";}"),
+            str("User{age: 30; name: \"al\";}")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "User has no field `admin`, its fields are name, age")]
+    fn a_constructor_cant_give_an_unknown_field() {
+        eval("class User{name; age;}; User{name: \"al\"; age: 3; admin: true;};");
+    }
+
+    #[test]
+    #[should_panic(expected = "User is missing the field `age`")]
+    fn a_constructor_has_to_give_every_field() {
+        eval("class User{name; age;}; User{name: \"al\";};");
+    }
+
+    #[test]
+    #[should_panic(expected = "class User already declares its fields")]
+    fn a_class_declares_its_fields_once() {
+        eval("class User{name; age;}; class User{name;};");
+    }
+
+    #[test]
+    fn positional_instances_have_any_arity() {
+        let program = "
+            class Pair;
+            class Point{x; y;};
+            items := [2; 3;];
+        ";
+        assert_eq!(show(program, "Pair[1; 2;]"), str("Pair[1; 2;]"));
+        assert_eq!(show(program, "Pair[1; ...items;]"), str("Pair[1; 2; 3;]"));
+        assert_eq!(show(program, "Point[1; 2;]"), str("Point{x: 1; y: 2;}"));
+        assert_eq!(
+            show(program, "Point[1; 2;] == Point{x: 1; y: 2;}"),
+            str("true")
+        );
+    }
+
+    #[test]
+    fn positional_instances_read_by_index_and_print_empty() {
+        let program = "
+            class V;
+            class Point{x; y;};
+        ";
+        assert_eq!(show(program, "V[]"), str("V[]"));
+        assert_eq!(show(program, "V{}"), str("V[]"));
+        assert_eq!(show(program, "{}"), str("{}"));
+        assert_eq!(eval_after(program, "V[1; 2;] 0;"), Object::Int(1));
+        assert_eq!(eval_after(program, "V[1; 2;] (0 - 1;);"), Object::Int(2));
+        assert_eq!(eval_after(program, "V[1; 2;] 5;"), Object::Nil);
+        assert_eq!(eval_after(program, "Point[1; 2;] 1;"), Object::Int(2));
+        assert_eq!(show(program, "V[1;] :responds_to? 0"), str("true"));
+        assert_eq!(show(program, "V{a: 1;} :responds_to? 0"), str("false"));
+    }
+
+    #[test]
+    fn positional_patterns_destructure_in_order() {
+        let program = "
+            class Pair;
+            class Point{x; y;};
+            sum := object
+              def Pair[a; b;] -> a + b;
+              def Pair[a; ...rest;] -> rest;
+              def Point[x; _;] -> x;
+            end;
+        ";
+        assert_eq!(eval_after(program, "sum Pair[1; 2;];"), Object::Int(3));
+        assert_eq!(eval_after(program, "sum Pair[1; 2; 3;];"), ints(&[2, 3]));
+        assert_eq!(
+            eval_after(program, "sum Point{y: 2; x: 1;};"),
+            Object::Int(1)
+        );
+    }
+
+    #[test]
+    fn ranges_go_through_their_ints() {
+        let program = "square := object def n -> n * n; end;";
+        assert_eq!(
+            eval_after(program, "1..=3. :map square. :to_list;"),
+            ints(&[1, 4, 9])
+        );
+        assert_eq!(eval_after(program, "1..4. :to_list;"), ints(&[1, 2, 3]));
+        assert_eq!(eval_after(program, "3..1. :to_list;"), ints(&[]));
+        assert_eq!(show(program, "1..10. :includes? 10"), str("false"));
+        assert_eq!(show(program, "1..=10. :includes? 10"), str("true"));
+        assert_eq!(show(program, "1..=10"), str("1..=10"));
+    }
+
+    #[test]
+    fn anything_answering_next_can_be_iterated_lazily() {
+        let program = "
+            class Naturals
+              def :next -> IterResult[self :from; Naturals{from: self :from. + 1;};];
+            end
+            odd := object
+              def n -> n % 2. == 1;
+            end;
+        ";
+        assert_eq!(
+            eval_after(program, "Naturals{from: 1;} :take 3. :to_list;"),
+            ints(&[1, 2, 3])
+        );
+        assert_eq!(
+            eval_after(
+                program,
+                "Naturals{from: 1;} :filter odd. :take 3. :to_list;"
+            ),
+            ints(&[1, 3, 5])
+        );
+        assert_eq!(
+            eval_after(program, "Naturals{from: 1;} :take 4. :reduce +;"),
+            Object::Int(10)
+        );
+        assert_eq!(
+            eval_after(program, "[1; 2;] :iter. :next. :value;"),
+            Object::Int(1)
+        );
+        assert_eq!(show(program, "[] :iter. :next"), str("IterEnd[]"));
+    }
+
+    #[test]
+    fn a_sym_is_only_equal_to_itself() {
+        let program = "
+            step := Sym[:step;];
+            class Counter
+              def ^step -> Counter{n: self :n. + 1;};
+            end
+            r := {^step: 1;};
+        ";
+        assert_eq!(show(program, "step == Sym[:step;]"), str("false"));
+        assert_eq!(show(program, "step == step"), str("true"));
+        assert_eq!(show(program, "Counter{n: 1;} step"), str("Counter{n: 2;}"));
+        assert_eq!(eval_after(program, "r step;"), Object::Int(1));
+    }
+
+    #[test]
+    fn a_placeholder_replays_the_messages_it_was_sent() {
+        assert_eq!(eval("inc := _ + 1; inc 10;"), Object::Int(11));
+        assert_eq!(eval("[1; 2; 3;] :map (_ * 2;);"), ints(&[2, 4, 6]));
+        assert_eq!(eval("[1; 2; 3;] :map (_ * 2;) (_ + 1;);"), ints(&[3, 5, 7]));
+    }
+
+    #[test]
+    fn a_record_with_placeholders_fills_them_in() {
+        let program = "
+            fizzbuzz := object
+              def {fizz: 0; buzz: 0;} -> \"fizzbuzz\";
+              def {fizz: 0;} -> \"fizz\";
+              def {buzz: 0;} -> \"buzz\";
+              def {n;} -> n;
+            end;
+        ";
+        assert_eq!(
+            eval_after(
+                program,
+                "[3; 5; 15; 7;] :map {fizz: _ % 3; buzz: _ % 5; n: _;} fizzbuzz;"
+            ),
+            Object::List(vec![
+                str("fizz"),
+                str("buzz"),
+                str("fizzbuzz"),
+                Object::Int(7)
+            ])
+        );
+    }
+
+    #[test]
+    fn lists_have_a_native_library() {
+        let program = "
+            l := [3; 1; 2;];
+            even := object def n -> n % 2. == 0; end;
+        ";
+        assert_eq!(eval_after(program, "l :sort;"), ints(&[1, 2, 3]));
+        assert_eq!(
+            eval_after(program, "l :sort (object def a b -> a > b; end;);"),
+            ints(&[3, 2, 1])
+        );
+        assert_eq!(eval_after(program, "l ++ [4;];"), ints(&[3, 1, 2, 4]));
+        assert_eq!(eval_after(program, "l :fold 10 +;"), Object::Int(16));
+        assert_eq!(eval_after(program, "l :join \", \";"), str("3, 1, 2"));
+        assert_eq!(eval_after(program, "l (0 - 1;);"), Object::Int(2));
+        assert_eq!(eval_after(program, "l 3;"), Object::Nil);
+        assert_eq!(eval_after(program, "l :take 2;"), ints(&[3, 1]));
+        assert_eq!(eval_after(program, "l :drop 5;"), ints(&[]));
+        assert_eq!(eval_after(program, "l :find even;"), Object::Int(2));
+        assert_eq!(
+            eval_after(program, "l :zip [:a; :b;];"),
+            Object::List(vec![
+                Object::List(vec![Object::Int(3), keyword("a")]),
+                Object::List(vec![Object::Int(1), keyword("b")]),
+            ])
+        );
+        assert_eq!(show(program, "l :all? even"), str("false"));
+    }
+
+    #[test]
+    fn a_handler_on_a_builtin_class_comes_before_the_native() {
+        let program = "
+            class List
+              def :sum -> self :reduce +;
+              def :map f -> self :native :map f. :reverse;
+            end
+            class Int
+              def + Str[s;] -> s;
+            end
+        ";
+        assert_eq!(eval_after(program, "[1; 2; 3;] :sum;"), Object::Int(6));
+        assert_eq!(
+            eval_after(program, "[1; 2; 3;] :map (_ + 1;);"),
+            ints(&[4, 3, 2])
+        );
+        // `:native` skips the handler
+        assert_eq!(
+            eval_after(program, "[1; 2; 3;] :native :map (_ + 1;);"),
+            ints(&[2, 3, 4])
+        );
+        assert_eq!(eval_after(program, "1 + 2;"), Object::Int(3));
+        assert_eq!(eval_after(program, "1 + \"a\";"), str("a"));
+    }
+
+    #[test]
+    fn strings_count_graphemes() {
+        // "é" is written as `e` & a combining accent
+        let program = "word := \"he\u{301}llo\";";
+        assert_eq!(eval_after(program, "word :len;"), Object::Int(5));
+        assert_eq!(eval_after(program, "word (1..3;);"), str("e\u{301}l"));
+        assert_eq!(eval_after(program, "word 1;"), str("e\u{301}"));
+        assert_eq!(eval_after(program, "word :chars. :len;"), Object::Int(5));
+        assert_eq!(eval_after(program, "\"👍🏽!\" :len;"), Object::Int(2));
+    }
+
+    #[test]
+    fn str_to_i_gives_a_result() {
+        assert_eq!(show("", "\"42\" :to_i"), str("Ok{value: 42;}"));
+        assert_eq!(
+            show("", "\"4x2\" :to_i"),
+            str("Err{error: \"`4x2` isn't an integer\";}")
+        );
+        assert_eq!(eval("\"0\" :to_i. :value;"), Object::Int(0));
+    }
+
+    #[test]
+    fn strings_have_a_native_library() {
+        assert_eq!(eval("\"a\" + \"b\";"), str("ab"));
+        assert_eq!(
+            eval("\"a,b\" :split \",\";"),
+            Object::List(vec![str("a"), str("b")])
+        );
+        assert_eq!(eval("\" a \" :trim. :upcase;"), str("A"));
+        assert_eq!(eval("\"aXa\" :replace \"a\" \"b\";"), str("bXb"));
+        assert_eq!(show("", "\"abc\" :starts_with? \"ab\""), str("true"));
+        assert_eq!(show("", "\"a\" < \"b\""), str("true"));
+    }
+
+    #[test]
+    fn dict_put_and_remove_give_a_new_dict() {
+        let program = "d := #{:a => 1; \"b\" => 2;};";
+        assert_eq!(
+            show(program, "d :put :c 3"),
+            str("#{:a => 1; \"b\" => 2; :c => 3;}")
+        );
+        // putting a key that's there keeps its place
+        assert_eq!(show(program, "d :put :a 5"), str("#{:a => 5; \"b\" => 2;}"));
+        assert_eq!(show(program, "d :remove :a"), str("#{\"b\" => 2;}"));
+        assert_eq!(
+            show(program, "d :remove :z"),
+            str("#{:a => 1; \"b\" => 2;}")
+        );
+        assert_eq!(show(program, "d :put :c 3. :remove :c. == d"), str("true"));
+        assert_eq!(eval_after(program, "d :put :c 3; d :len;"), Object::Int(2));
+        assert_eq!(eval_after(program, "d :get \"b\";"), Object::Int(2));
+        assert_eq!(eval_after(program, "d :get :z;"), Object::Nil);
+        assert_eq!(
+            show(program, "d :merge #{:a => 5; :c => 6;}"),
+            str("#{:a => 5; \"b\" => 2; :c => 6;}")
+        );
+    }
+
+    #[test]
+    fn dict_keys_can_be_any_value() {
+        let program = "
+            class Point;
+            d := #{[1; 2;] => :list; Point{x: 1;} => :point; (1..3;) => :range;};
+        ";
+        assert_eq!(eval_after(program, "d :get [1; 2;];"), keyword("list"));
+        assert_eq!(
+            eval_after(program, "d :get Point{x: 1;};"),
+            keyword("point")
+        );
+        assert_eq!(eval_after(program, "d :get (1..3;);"), keyword("range"));
+    }
+
+    #[test]
+    fn a_dict_pattern_needs_only_the_keys_it_names() {
+        let program = "
+            greet := object
+              def #{:name => n; :age => 3;} -> \"hi little \" + n;
+              def #{:name => n;} -> \"hi \" + n;
+              def _ -> \"hi\";
+            end;
+        ";
+        assert_eq!(
+            eval_after(program, "greet #{:name => \"al\"; :age => 3;};"),
+            str("hi little al")
+        );
+        assert_eq!(
+            eval_after(program, "greet #{:age => 4; :name => \"al\";};"),
+            str("hi al")
+        );
+        assert_eq!(eval_after(program, "greet #{:age => 3;};"), str("hi"));
+        assert_eq!(eval_after(program, "greet {name: \"al\";};"), str("hi"));
+    }
+
+    #[test]
+    fn set_operations_keep_first_added_order() {
+        let program = "
+            a := Set[3; 1; 2; 1;];
+            b := [4; 2; 3;] :to_set;
+        ";
+        assert_eq!(show(program, "a"), str("Set[3; 1; 2;]"));
+        assert_eq!(show(program, "a | b"), str("Set[3; 1; 2; 4;]"));
+        assert_eq!(show(program, "b | a"), str("Set[4; 2; 3; 1;]"));
+        assert_eq!(show(program, "a & b"), str("Set[3; 2;]"));
+        assert_eq!(show(program, "b & a"), str("Set[2; 3;]"));
+        assert_eq!(show(program, "a - b"), str("Set[1;]"));
+        assert_eq!(show(program, "a :add 0. :remove 3"), str("Set[1; 2; 0;]"));
+        assert_eq!(show(program, "a :includes? 2"), str("true"));
+        assert_eq!(eval_after(program, "a :to_list;"), ints(&[3, 1, 2]));
+        assert_eq!(show(program, "Set[1; 2;] == Set[2; 1;]"), str("true"));
+    }
+
+    #[test]
+    fn instances_describe_themselves() {
+        let program = "
+            class Point{x; y;}
+              def :move dx dy -> Point[self :x. + dx; self :y. + dy;];
+            end
+            p := Point[1; 2;];
+        ";
+        assert_eq!(show(program, "p :class"), str("[Point]"));
+        assert_eq!(show(program, "p :class. == Point"), str("true"));
+        assert_eq!(eval_after(program, "p :fields;"), eval("[:x; :y;];"));
+        assert_eq!(eval_after(program, "Point :fields;"), eval("[:x; :y;];"));
+        assert_eq!(
+            eval_after(program, "Point :handlers;"),
+            Object::List(vec![str("def :move dx dy")])
+        );
+        assert_eq!(show(program, "p :responds_to? :move 1 2"), str("true"));
+        assert_eq!(show(program, "p :responds_to? :move 1"), str("false"));
+        assert_eq!(show(program, "p :responds_to? :x"), str("true"));
+        assert_eq!(show(program, "p :responds_to? :area"), str("false"));
+    }
+
+    #[test]
+    fn a_handler_comes_before_a_field_with_its_name() {
+        let program = "
+            class Temp
+              def :celsius -> self :kelvin. - 273;
+              def :kelvin -> 300;
+            end
+        ";
+        assert_eq!(
+            eval_after(program, "Temp{kelvin: 10;} :kelvin;"),
+            Object::Int(300)
+        );
+        assert_eq!(
+            eval_after(program, "Temp{kelvin: 10;} :celsius;"),
+            Object::Int(27)
+        );
+        // built-in messages come before fields too
+        assert_eq!(eval("{fields: 1;} :fields;"), eval("[:fields;];"));
+    }
+
+    #[test]
+    fn a_private_field_is_only_read_through_self() {
+        let program = "
+            class Account{_cents; owner;}
+              def :dollars -> self :_cents. / 100;
+            end
+            a := Account{_cents: 500; owner: \"jo\";};
+        ";
+        assert_eq!(eval_after(program, "a :dollars;"), Object::Int(5));
+        assert_eq!(eval_after(program, "a :owner;"), str("jo"));
+        assert_eq!(eval_after(program, "a :fields;"), eval("[:owner;];"));
+        assert_eq!(show(program, "a :responds_to? :_cents"), str("false"));
+    }
+
+    #[test]
+    #[should_panic(expected = "`_cents` is private to Account")]
+    fn a_private_field_cant_be_read_from_outside() {
+        eval("class Account{_cents;}; Account{_cents: 500;} :_cents;");
+    }
+
+    #[test]
+    fn and_and_or_only_call_the_right_side_when_needed() {
+        let program = "
+            calls := Cell[0;];
+            counted := object
+              def :call -> calls :update (_ + 1;). > 0;
+            end;
+        ";
+        assert_eq!(
+            eval_after(program, "false && counted; calls :get;"),
+            Object::Int(0)
+        );
+        assert_eq!(
+            eval_after(program, "true || counted; calls :get;"),
+            Object::Int(0)
+        );
+        assert_eq!(
+            eval_after(program, "true && counted; calls :get;"),
+            Object::Int(1)
+        );
+        assert_eq!(
+            eval_after(program, "false || counted; calls :get;"),
+            Object::Int(1)
+        );
+        assert_eq!(show(program, "true && counted"), str("true"));
+        assert_eq!(show(program, "true && false"), str("false"));
+        assert_eq!(show(program, "false || true"), str("true"));
+    }
+
+    #[test]
+    fn then_else_only_calls_the_branch_taken() {
+        let program = "
+            big := object def :call -> \"big\"; end;
+            small := object def :call -> \"small\"; end;
+            size := object def n -> (n > 10;) :then big :else small; end;
+        ";
+        assert_eq!(eval_after(program, "size 11;"), str("big"));
+        assert_eq!(eval_after(program, "size 1;"), str("small"));
+        // a plain value answers `:call` with itself
+        assert_eq!(
+            eval_after(program, "false :then 1 :else 2;"),
+            Object::Int(2)
+        );
+        assert_eq!(show(program, "true :not"), str("false"));
+    }
+
+    #[test]
+    fn a_wildcard_handler_doesnt_take_over_to_s() {
+        let program = "
+            o := object def n -> n * 2; end;
+            class P def :to_s -> \"<p>\"; end
+            class Q def :to_s -> 5; end
+        ";
+        assert_eq!(show(program, "[o;]"), str("[<anon class>{};]"));
+        assert_eq!(show(program, "[P{};]"), str("[<p>;]"));
+        // a `:to_s` that isn't a Str falls back to the native format
+        assert_eq!(show(program, "[Q{};]"), str("[Q[];]"));
+    }
+
+    #[test]
+    fn a_wildcard_handler_doesnt_take_over_equality_or_hashing() {
+        let program = "
+            class Pairer def a b -> :hijacked; end
+            class Doubler def n -> n * 2; end
+            o := object def n -> n * 2; end;
+            pinned := object
+              def ^(Pairer{};) -> :pinned;
+              def _ -> :other;
+            end;
+        ";
+        assert_eq!(show(program, "Pairer{} == Pairer{}"), str("true"));
+        assert_eq!(eval_after(program, "o :to_s;"), str("<anon class>{}"));
+        assert_eq!(eval_after(program, "pinned 1;"), keyword("other"));
+        assert_eq!(
+            eval_after(program, "#{Doubler{} => 1;} :get Doubler{};"),
+            Object::Int(1)
+        );
+    }
+
+    #[test]
+    fn native_collections_answer_the_iterator_protocol() {
+        assert_eq!(eval("1..=5. :take 2. :to_list;"), ints(&[1, 2]));
+        assert_eq!(eval("1..=2000. :reduce +;"), Object::Int(2001000));
+        assert_eq!(
+            eval("\"ab\" :map (_ :upcase;). :to_list;"),
+            Object::List(vec![str("A"), str("B")])
+        );
+        assert_eq!(eval("Set[1; 2;] :reduce +;"), Object::Int(3));
+    }
+
+    #[test]
+    fn list_filter_and_any_use_truthiness() {
+        let program = "one := object def _ -> 1; end;";
+        assert_eq!(
+            eval_after(program, "[1; 2; 3;] :filter one;"),
+            ints(&[1, 2, 3])
+        );
+        assert_eq!(show(program, "[1; 2; 3;] :any? one"), str("true"));
+        assert_eq!(eval("[1; 2; 3;] :filter (_ > 1;);"), ints(&[2, 3]));
+    }
+
+    #[test]
+    fn symbol_keys_print_as_the_symbol() {
+        let program = "step := Sym[:step;]; r := {^step: 1; a: 2;};";
+        assert_eq!(show(program, "r"), str("{Sym[:step;]: 1; a: 2;}"));
+        assert_eq!(
+            show(program, "r :fields. :first. :identical? step"),
+            str("true")
+        );
+    }
+
+    #[test]
+    fn an_inclusive_range_can_end_at_the_largest_int() {
+        let max = i64::MAX;
+        let program = "x := 9223372036854775806; range := x..=(x + 1;);";
+        assert_eq!(
+            eval_after(program, "range :to_list;"),
+            ints(&[max - 1, max])
+        );
+        assert_eq!(
+            eval_after(program, "range :iter. :to_list;"),
+            ints(&[max - 1, max])
+        );
+    }
+
+    #[test]
+    fn responds_to_counts_natives_without_running_them() {
+        assert_eq!(show("", "[1; 2;] :responds_to? :len"), str("true"));
+        assert_eq!(show("", "\"a\" :responds_to? :len"), str("true"));
+        assert_eq!(show("", "1 :responds_to? + 2"), str("true"));
+        assert_eq!(show("", "1 :responds_to? + \"a\""), str("false"));
+        assert_eq!(show("", "{x: 1;} :responds_to? :log"), str("true"));
+        assert_eq!(show("", "{x: 1;} :responds_to? :x"), str("true"));
+        assert_eq!(show("", "[1;] :responds_to? :nope"), str("false"));
+        // `:each` isn't run, so nothing is printed
+        assert_eq!(
+            show(
+                "",
+                "[1;] :responds_to? :each (object def n -> n :log; end;)"
+            ),
+            str("true")
+        );
+    }
+
+    #[test]
+    fn responds_to_native_asks_the_natives_alone() {
+        let program = "
+            class List
+              def :shout -> self :map (_ :to_s;). :join \"!\";
+            end
+        ";
+        assert_eq!(
+            show(program, "[1; 2;] :responds_to? :native :len"),
+            str("true")
+        );
+        assert_eq!(eval_after(program, "[1; 2;] :native :len;"), Object::Int(2));
+        assert_eq!(show(program, "[1;] :responds_to? :shout"), str("true"));
+        assert_eq!(
+            show(program, "[1;] :responds_to? :native :shout"),
+            str("false")
+        );
+        assert_eq!(
+            show(program, "\"ab\" :responds_to? :native :to_list"),
+            str("true")
+        );
+        assert_eq!(
+            eval_after(program, "\"ab\" :native :to_list;"),
+            Object::List(vec![str("a"), str("b")])
+        );
+        assert_eq!(
+            show(program, "[1;] :responds_to? :native :to_s"),
+            str("true")
+        );
+        assert_eq!(eval_after(program, "[1;] :native :to_s;"), str("[1;]"));
+        assert_eq!(
+            show(program, "[1;] :responds_to? :native :take :a"),
+            str("false")
+        );
+    }
+
+    #[test]
+    fn a_wildcard_handler_doesnt_hide_fields() {
+        let program = "
+            class Adder
+              def n -> n + (self :base;);
+            end
+            class Wallet{_cents;}
+              def n -> self :_cents. + n;
+            end
+        ";
+        assert_eq!(eval_after(program, "Adder{base: 10;} 5;"), Object::Int(15));
+        assert_eq!(
+            eval_after(program, "Adder{base: 10;} :base;"),
+            Object::Int(10)
+        );
+        assert_eq!(
+            eval_after(program, "Wallet{_cents: 10;} 5;"),
+            Object::Int(15)
+        );
+    }
+
+    #[test]
+    fn a_field_gives_way_to_its_handler_then_to_natives() {
+        let program = "
+            step := Sym[:step;];
+            class Gauge
+              def :level -> self :raw. * 2;
+              def ^step -> :stepped;
+              def _ -> :anything;
+            end
+            g := Gauge{level: 1; raw: 5; fields: 0; ^step: 3;};
+        ";
+        assert_eq!(eval_after(program, "g :level;"), Object::Int(10));
+        assert_eq!(eval_after(program, "g step;"), keyword("stepped"));
+        assert_eq!(eval_after(program, "g :raw;"), Object::Int(5));
+        assert_eq!(eval_after(program, "g :fields. :len;"), Object::Int(4));
+        assert_eq!(eval_after(program, "g :other;"), keyword("anything"));
+    }
+
+    #[test]
+    fn sort_survives_a_comparator_that_isnt_an_order() {
+        let program = "
+            odd := object def a b -> (a % 3;) == (b % 5;); end;
+            never := object def _ _ -> false; end;
+        ";
+        assert_eq!(
+            eval_after(program, "1..=40. :to_list. :sort odd. :len;"),
+            Object::Int(40)
+        );
+        // every item is still there once
+        assert_eq!(
+            eval_after(program, "1..=40. :to_list. :sort odd. :sort;"),
+            eval("1..=40. :to_list;")
+        );
+        // items that are never before each other keep their order
+        assert_eq!(
+            eval_after(program, "[3; 1; 2;] :sort never;"),
+            ints(&[3, 1, 2])
+        );
+    }
+
+    #[test]
+    fn take_and_drop_treat_a_negative_count_as_zero() {
+        assert_eq!(eval("[1; 2;] :take (0 - 1;);"), ints(&[]));
+        assert_eq!(eval("[1; 2;] :drop (0 - 1;);"), ints(&[1, 2]));
+        assert_eq!(eval("1..=5. :iter. :take (0 - 1;). :to_list;"), ints(&[]));
+    }
+
+    #[test]
+    #[should_panic(expected = "the family Bool is already declared")]
+    fn a_family_cant_be_declared_again() {
+        eval("class Bool = Maybe;");
+    }
+
+    #[test]
+    #[should_panic(expected = "A is already in the family Shape")]
+    fn a_class_belongs_to_one_family() {
+        eval("class Shape = A | B; class Other = A | C;");
+    }
+
+    #[test]
+    fn a_family_matches_each_of_its_members() {
+        let program = "
+            class Shape = A | B;
+            class Other = C;
+            shape := object
+              def Shape{} -> :shape;
+              def _ -> :other;
+            end;
+        ";
+        assert_eq!(eval_after(program, "shape A{};"), keyword("shape"));
+        assert_eq!(eval_after(program, "shape B{};"), keyword("shape"));
+        assert_eq!(eval_after(program, "shape C{};"), keyword("other"));
+    }
+
+    #[test]
+    fn or_and_as_patterns_nest() {
+        let program = "
+            class Pair;
+            first_two := object def [1 | 2; x;] -> x; def _ -> :no; end;
+            field := object def {a: n @ 1 | 2;} -> n; def _ -> :no; end;
+            inner := object def [p @ [1; _;]; x;] -> [p; x;]; def _ -> :no; end;
+            either := object def x @ [a;] | x @ [a; _;] -> a; def _ -> :no; end;
+            keyed := object def #{:k => 1 | 2;} -> :yes; def _ -> :no; end;
+            pair := object def Pair[1 | 2; b;] -> b; def _ -> :no; end;
+        ";
+        assert_eq!(eval_after(program, "first_two [2; 5;];"), Object::Int(5));
+        assert_eq!(eval_after(program, "first_two [3; 5;];"), keyword("no"));
+        assert_eq!(eval_after(program, "field {a: 2;};"), Object::Int(2));
+        assert_eq!(eval_after(program, "field {a: 3;};"), keyword("no"));
+        assert_eq!(
+            eval_after(program, "inner [[1; 2;]; 3;];"),
+            eval("[[1; 2;]; 3;];")
+        );
+        assert_eq!(eval_after(program, "inner [[2; 2;]; 3;];"), keyword("no"));
+        assert_eq!(eval_after(program, "either [1;];"), Object::Int(1));
+        assert_eq!(eval_after(program, "either [1; 2;];"), Object::Int(1));
+        assert_eq!(eval_after(program, "either [];"), keyword("no"));
+        assert_eq!(eval_after(program, "keyed #{:k => 2;};"), keyword("yes"));
+        assert_eq!(eval_after(program, "pair Pair[2; 5;];"), Object::Int(5));
+        assert_eq!(eval_after(program, "pair Pair[3; 5;];"), keyword("no"));
+    }
+
+    #[test]
+    fn range_map_and_filter_are_lazy() {
+        assert_eq!(
+            eval("1..=1000000000000. :map (_ * 2;). :take 3. :to_list;"),
+            ints(&[2, 4, 6])
+        );
+        assert_eq!(
+            eval("1..1000000000000. :filter (_ % 7. == 0;). :take 2. :to_list;"),
+            ints(&[7, 14])
+        );
+        assert_eq!(show("", "1..=3. :map (_ + 1;)"), str("Iter[...]"));
+        // collections keep their kind
+        assert_eq!(eval("[1; 2;] :map (_ + 1;);"), ints(&[2, 3]));
+        assert_eq!(show("", "Set[1; 2;] :map (_ + 1;)"), str("Set[2; 3;]"));
+        assert_eq!(
+            show("", "#{:a => 1;} :map (object def k v -> v + 1; end;)"),
+            str("#{:a => 2;}")
+        );
+    }
+
+    #[test]
+    fn a_placeholder_chain_takes_keyword_messages() {
+        let program = "shout := _ :trim. :upcase;";
+        assert_eq!(eval_after(program, "shout \" hi \";"), str("HI"));
+        assert_eq!(
+            eval_after(program, "[\" a \"; \"b \";] :map (_ :trim. :upcase;);"),
+            Object::List(vec![str("A"), str("B")])
+        );
+        // `:call` applies a chain to a keyword, `:map` does it for each item
+        assert_eq!(eval("(_ :to_s;) :call :a;"), str(":a"));
+        assert_eq!(
+            eval("[:a; :b;] :map (_ :to_s;);"),
+            Object::List(vec![str(":a"), str(":b")])
+        );
+        assert_eq!(eval("[:a;] :filter (_ == :a;);"), eval("[:a;];"));
+    }
+
+    #[test]
+    fn log_and_inspect_show_a_placeholder() {
+        assert_eq!(eval("f := _ :len; f :log;"), Object::Nil);
+        assert_eq!(eval("f := _ + 1; f :inspect;"), str("_ + 1"));
+        assert_eq!(eval("x := _; x :inspect;"), str("_"));
+        assert_eq!(eval("f := _ :len. * 2; f :inspect;"), str("_ :len. * 2"));
+    }
+
+    #[test]
+    #[should_panic(expected = "`9223372036854775807 + 1` overflows an Int")]
+    fn adding_past_the_largest_int_is_an_error() {
+        eval("9223372036854775807 + 1;");
+    }
+
+    #[test]
+    #[should_panic(expected = "`2 ** 70` overflows an Int")]
+    fn a_power_past_the_largest_int_is_an_error() {
+        eval("2 ** 70;");
+    }
+
+    #[test]
+    #[should_panic(expected = "`-9223372036854775808 / -1` overflows an Int")]
+    fn dividing_the_smallest_int_by_minus_one_is_an_error() {
+        eval("min := 0 - 9223372036854775807. - 1; min / (0 - 1;);");
+    }
+
+    #[test]
+    #[should_panic(expected = "`1 % 0` divides by zero")]
+    fn a_remainder_by_zero_is_an_error() {
+        eval("1 % 0;");
+    }
+
+    #[test]
+    fn arithmetic_up_to_the_limits_is_fine() {
+        assert_eq!(eval("9223372036854775806 + 1;"), Object::Int(i64::MAX));
+        assert_eq!(eval("2 ** 62;"), Object::Int(1 << 62));
+        assert_eq!(
+            eval("min := 0 - 9223372036854775807. - 1; min / 1;"),
+            Object::Int(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn a_send_puts_the_senders_self_back() {
+        let program = "
+            class Limit{max;}
+              def n -> n :min (self :max;);
+            end
+            class Int
+              def :min m -> [self; m;] :sort. :first;
+              def :to_s -> \"an int\";
+            end
+            class Probe{name;}
+              def :check l -> [l 3; self :name;];
+            end
+        ";
+        assert_eq!(
+            eval_after(program, "Probe{name: :probe;} :check Limit{max: 2;};"),
+            Object::List(vec![Object::Int(2), keyword("probe")])
+        );
+        assert_eq!(eval_after(program, "5 :to_s;"), str("an int"));
+        assert_eq!(eval_after(program, "5 :native :to_s;"), str("5"));
+    }
+
+    #[test]
+    fn a_field_isnt_looked_up_through_a_pattern_that_pins_it() {
+        let program = "
+            class Limit{max;}
+              def ^(self :max;) -> :at_max;
+              def n -> :under;
+            end
+            class Gauge{level;}
+              def :level when false -> 0;
+            end
+        ";
+        assert_eq!(eval_after(program, "Limit{max: 3;} 3;"), keyword("at_max"));
+        assert_eq!(eval_after(program, "Limit{max: 3;} 1;"), keyword("under"));
+        assert_eq!(eval_after(program, "Limit{max: 3;} :max;"), Object::Int(3));
+        // the guard fails, so the field answers
+        assert_eq!(
+            eval_after(program, "Gauge{level: 3;} :level;"),
+            Object::Int(3)
+        );
+    }
+}
@@ -8,6 +8,7 @@ pub enum Token {
     Class(usize),
     Id(String, usize),
     OpenBrace(usize),
+    OpenDict(usize),
     CloseBrace(usize),
     OpenSqBrace(usize),
     CloseSqBrace(usize),
@@ -21,6 +22,7 @@ pub enum Token {
     Operator(String, usize),
     Str(String, usize),
     Caret(usize),
+    At(usize),
     Spread(usize),
     Object(usize),
     Comment(String, usize),
@@ -87,6 +89,12 @@ impl Token {
             _ => None,
         }
     }
+    pub fn as_open_dict(&self) -> Option<()> {
+        match self {
+            Token::OpenDict(_) => Some(()),
+            _ => None,
+        }
+    }
     pub fn as_close_brace(&self) -> Option<()> {
         match self {
             Token::CloseBrace(_) => Some(()),
@@ -136,6 +144,12 @@ impl Token {
             _ => None,
         }
     }
+    pub fn as_at(&self) -> Option<()> {
+        match self {
+            Token::At(_) => Some(()),
+            _ => None,
+        }
+    }
     pub fn as_open_paren(&self) -> Option<()> {
         match self {
             Token::OpenParen(_) => Some(()),
@@ -174,16 +188,16 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
     let end_chars = HashSet::from([".", ";", " ", "}", ")", "\n"]);
 
     let one_char_operators = HashSet::from(["+", "-", "*", "/", "%", ">", "<", "=", "|", "&"]);
-    let two_char_operators = HashSet::from(["**", ">=", "<=", "==", "&&", "||", "..", "|>"]);
+    let two_char_operators =
+        HashSet::from(["**", ">=", "<=", "==", "&&", "||", "..", "|>", "++", "=>"]);
     let three_char_operators = HashSet::from(["..="]);
 
     while idx < program_string.len() {
         if program_string.get(idx..=idx + 1) == Some("--") {
             let original_idx = idx;
             idx += 2;
-            let comment = program_string
+            let comment = program_string[idx..]
                 .chars()
-                .skip(idx)
                 .take_while(|t| *t != '\n')
                 .collect::<String>();
             idx += comment.len();
@@ -198,6 +212,10 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
             let original_idx = idx;
             idx += 1;
             tokens.push(Token::Caret(original_idx))
+        } else if program_string.get(idx..=idx) == Some("@") {
+            let original_idx = idx;
+            idx += 1;
+            tokens.push(Token::At(original_idx))
         } else if program_string.get(idx..=idx) == Some("(") {
             let original_idx = idx;
             idx += 1;
@@ -231,32 +249,31 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
             idx += 6;
             tokens.push(Token::Object(original_idx))
         } else if let Some(op) = program_string
-            .get(idx..=idx)
-            .filter(|item| one_char_operators.get(item).is_some())
+            .get(idx..=idx + 2)
+            .filter(|item| three_char_operators.contains(item))
         {
             let original_idx = idx;
-            idx += 1;
+            idx += 3;
             tokens.push(Token::Operator(op.to_string(), original_idx))
         } else if let Some(op) = program_string
             .get(idx..=idx + 1)
-            .filter(|item| two_char_operators.get(item).is_some())
+            .filter(|item| two_char_operators.contains(item))
         {
             let original_idx = idx;
             idx += 2;
             tokens.push(Token::Operator(op.to_string(), original_idx))
         } else if let Some(op) = program_string
-            .get(idx..=idx + 2)
-            .filter(|item| three_char_operators.get(item).is_some())
+            .get(idx..=idx)
+            .filter(|item| one_char_operators.contains(item))
         {
             let original_idx = idx;
-            idx += 3;
+            idx += 1;
             tokens.push(Token::Operator(op.to_string(), original_idx))
         } else if program_string.get(idx..=idx) == Some(":") {
             let original_idx = idx;
             idx += 1;
-            let name = program_string
+            let name = program_string[idx..]
                 .chars()
-                .skip(idx)
                 .take_while(|c| !end_chars.contains(c.to_string().as_str()))
                 .collect::<String>();
             idx += name.len();
@@ -269,6 +286,10 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
             let original_idx = idx;
             idx += 1;
             tokens.push(Token::EndToken(original_idx));
+        } else if program_string.get(idx..=idx + 1) == Some("#{") {
+            let original_idx = idx;
+            idx += 2;
+            tokens.push(Token::OpenDict(original_idx));
         } else if program_string.get(idx..=idx) == Some("{") {
             let original_idx = idx;
             idx += 1;
@@ -296,9 +317,8 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
         } else if program_string.get(idx..=idx) == Some("\"") {
             let original_idx = idx;
             idx += 1;
-            let str: String = program_string
+            let str: String = program_string[idx..]
                 .chars()
-                .skip(idx)
                 .take_while(|x| *x != '"')
                 .collect();
             idx += 1 + str.len();
@@ -309,9 +329,8 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
             .unwrap_or(false)
         {
             let original_idx = idx;
-            let val: String = program_string
+            let val: String = program_string[idx..]
                 .chars()
-                .skip(idx)
                 .take_while(|x| x.is_numeric())
                 .collect();
             idx += val.len();
@@ -325,9 +344,8 @@ pub fn tokenize(program_string: String) -> Vec<Token> {
             .unwrap_or(false)
         {
             let original_idx = idx;
-            let name: String = program_string
+            let name: String = program_string[idx..]
                 .chars()
-                .skip(idx)
                 .take_while(|x| x.is_alphanumeric() || *x == '_')
                 .collect();
             idx += name.len();
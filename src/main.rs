@@ -1,20 +1,49 @@
-use crate::interpreter::interpret;
+use crate::interpreter::{interpret, Dispatch};
+use std::env;
 use std::fs;
 
+pub mod analyzer;
 pub mod interpreter;
 pub mod lexer;
 pub mod parser;
 
+// `position` is an index into the prelude followed by the program
+fn location(position: usize, prelude: &str, program: &str) -> String {
+    let (file, source, offset) = if position < prelude.len() {
+        ("src/prelude.pnt", prelude, position)
+    } else {
+        ("test.pnt", program, position - prelude.len())
+    };
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = offset - before.rfind('\n').map(|idx| idx + 1).unwrap_or(0) + 1;
+    format!("{}:{}:{}", file, line, column)
+}
+
 fn main() {
+    let dispatch = if env::args().any(|arg| arg == "--dispatch=specificity") {
+        Dispatch::Specificity
+    } else {
+        Dispatch::Declaration
+    };
+
     let prelude = fs::read_to_string("./src/prelude.pnt").unwrap();
     let test_program = fs::read_to_string("./test.pnt").unwrap();
-    let tokens = lexer::tokenize(prelude + &test_program);
+    let tokens = lexer::tokenize(prelude.clone() + &test_program);
     // println!("Tokens! {:?}", tokens);
 
     let ast = parser::Parser { tokens, idx: 0 }.parse();
 
     // println!("AST! {:?}", ast);
 
+    for warning in analyzer::check(&ast, dispatch) {
+        eprintln!(
+            "warning: {} ({})",
+            warning.message,
+            location(warning.position, &prelude, &test_program)
+        );
+    }
+
     // println!("{:?}", interpret(ast));
-    interpret(ast);
+    interpret(ast, dispatch);
 }
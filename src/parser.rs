@@ -3,10 +3,12 @@ use crate::lexer::Token;
 #[derive(Debug, PartialEq, Clone)]
 pub enum Node {
     Keyword(String),
-    Def(Vec<Node>, Box<Node>),
-    Class(String, Vec<Node>),
+    Def(Vec<Node>, Option<Box<Node>>, Box<Node>, usize),
+    Class(String, Vec<String>, Option<Vec<String>>, Vec<Node>),
     MethodCall(Box<Node>, Vec<Node>),
     RecordConstructor(String, Vec<(String, Node)>),
+    // Point{...p; x: 5;}
+    RecordUpdate(String, Box<Node>, Vec<(String, Node)>),
     VectorConstructor(String, Vec<Node>),
     Int(usize),
     IdLookup(String),
@@ -19,6 +21,10 @@ pub enum Node {
     Spread(Box<Node>),
     Object(Vec<Node>),
     RecordLiteral(Vec<(String, Node)>),
+    Or(Vec<Node>),
+    As(String, Box<Node>),
+    // #{:a => 1;}
+    Dict(Vec<(Node, Node)>),
 }
 
 #[derive(Clone)]
@@ -32,6 +38,13 @@ impl Parser {
         let mut ast: Vec<Node> = vec![];
 
         while self.tokens.get(self.idx).is_some() {
+            // a trailing comment isn't followed by an expression
+            if self.tokens[self.idx..]
+                .iter()
+                .all(|t| t.as_comment().is_some())
+            {
+                break;
+            }
             ast.push(self.parse_expr());
         }
 
@@ -61,6 +74,15 @@ impl Parser {
     }
 
     fn parse_expr(&mut self) -> Node {
+        // `name :=` is looked for before `scan` gets a chance to skip comments
+        while self
+            .tokens
+            .get(self.idx)
+            .and_then(|t| t.as_comment())
+            .is_some()
+        {
+            self.idx += 1;
+        }
         if let Some([Token::Id(_, _), Token::ColonEq(_)]) =
             self.tokens.get(self.idx..(self.idx + 2))
         {
@@ -135,6 +157,8 @@ impl Parser {
             self.parse_vector_constructor()
         } else if self.scan(|t| t.as_open_brace()) {
             self.parse_record_literal()
+        } else if self.scan(|t| t.as_open_dict()) {
+            self.parse_dict()
         } else if self.is_record_constructor() {
             self.parse_record_constructor()
         } else if self.scan(|t| t.as_id()) {
@@ -160,13 +184,18 @@ impl Parser {
     }
 
     fn parse_record_literal(&mut self) -> Node {
+        self.parse_record(Self::parse_expr)
+    }
+
+    // {a: 1;}, `value` parses what comes after each `name:`
+    fn parse_record(&mut self, value: fn(&mut Self) -> Node) -> Node {
         self.consume(|t| t.as_open_brace());
         let mut properties: Vec<(String, Node)> = vec![];
         while !self.scan(|t| t.as_close_brace()) {
-            let name = self.consume(|t| t.as_id());
+            let name = self.parse_prop_name();
             let expr = if self.scan(|t| t.as_colon()) {
                 self.consume(|t| t.as_colon());
-                self.parse_expr()
+                value(self)
             } else {
                 self.consume(|t| t.as_end_token());
                 Node::IdLookup(name.to_owned())
@@ -179,6 +208,35 @@ impl Parser {
         Node::RecordLiteral(properties)
     }
 
+    fn parse_dict(&mut self) -> Node {
+        self.parse_dict_with(Self::parse_expr)
+    }
+
+    fn parse_dict_with(&mut self, value: fn(&mut Self) -> Node) -> Node {
+        self.consume(|t| t.as_open_dict());
+        let mut entries: Vec<(Node, Node)> = vec![];
+        while !self.scan(|t| t.as_close_brace()) {
+            let key = self.parse_single_expr();
+            self.consume(|t| t.as_operator().filter(|op| op == "=>"));
+            entries.push((key, value(self)));
+        }
+        self.consume(|t| t.as_close_brace());
+        Node::Dict(entries)
+    }
+
+    // `^step` is a key named by the symbol `step`, it needs a value
+    fn parse_prop_name(&mut self) -> String {
+        if !self.scan(|t| t.as_caret()) {
+            return self.consume(|t| t.as_id());
+        }
+        self.consume(|t| t.as_caret());
+        let name = format!("^{}", self.consume(|t| t.as_id()));
+        if !self.scan(|t| t.as_colon()) {
+            panic!("`{}` needs a value, `{}: value;`", name, name);
+        }
+        name
+    }
+
     fn parse_object(&mut self) -> Node {
         self.consume(|t| t.as_object());
         let mut methods: Vec<Node> = vec![];
@@ -213,10 +271,14 @@ impl Parser {
     }
 
     fn parse_list_literal(&mut self) -> Node {
+        self.parse_list(Self::parse_expr)
+    }
+
+    fn parse_list(&mut self, element: fn(&mut Self) -> Node) -> Node {
         self.consume(|t| t.as_open_sq_brace());
         let mut elements: Vec<Node> = vec![];
         while !self.scan(|t| t.as_close_sq_brace()) {
-            elements.push(self.parse_expr());
+            elements.push(element(self));
         }
         self.consume(|t| t.as_close_sq_brace());
         Node::List(elements)
@@ -244,26 +306,44 @@ impl Parser {
     }
 
     fn parse_vector_constructor(&mut self) -> Node {
+        self.parse_vector(Self::parse_expr)
+    }
+
+    fn parse_vector(&mut self, element: fn(&mut Self) -> Node) -> Node {
         let name = self.consume(|t| t.as_id());
         self.consume(|t| t.as_open_sq_brace());
         let mut exprs = vec![];
         while !self.scan(|t| t.as_close_sq_brace()) {
-            exprs.push(self.parse_expr());
+            exprs.push(element(self));
         }
         self.consume(|t| t.as_close_sq_brace());
         Node::VectorConstructor(name, exprs)
     }
 
     fn parse_record_constructor(&mut self) -> Node {
+        self.parse_constructor(Self::parse_expr)
+    }
+
+    fn parse_constructor(&mut self, value: fn(&mut Self) -> Node) -> Node {
         let name = self.consume(|t| t.as_id());
         self.consume(|t| t.as_open_brace());
         let mut properties: Vec<(String, Node)> = vec![];
 
+        let base = if self.scan(|t| t.as_spread()) {
+            self.consume(|t| t.as_spread());
+            Some(self.parse_expr())
+        } else {
+            None
+        };
+
         while !self.scan(|t| t.as_close_brace()) {
-            let name = self.consume(|t| t.as_id());
+            if self.scan(|t| t.as_spread()) {
+                panic!("`...` has to be the first thing in `{}{{}}`", name);
+            }
+            let name = self.parse_prop_name();
             if self.scan(|t| t.as_colon()) {
                 self.consume(|t| t.as_colon());
-                properties.push((name.clone(), self.parse_expr()));
+                properties.push((name.clone(), value(self)));
             } else {
                 properties.push((name.clone(), Node::IdLookup(name.clone())));
                 self.consume(|t| t.as_end_token());
@@ -272,28 +352,153 @@ impl Parser {
         self.consume(|t| t.as_close_brace());
         properties.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
 
-        Node::RecordConstructor(name.clone(), properties)
+        match base {
+            Some(base) => Node::RecordUpdate(name, Box::new(base), properties),
+            None => Node::RecordConstructor(name, properties),
+        }
+    }
+
+    fn is_guard(&self) -> bool {
+        matches!(self.tokens.get(self.idx), Some(Token::Id(name, _)) if name == "when")
+    }
+
+    // def n when n > 10 -> ...
+    fn parse_guard(&mut self) -> Node {
+        self.consume(|t| t.as_id());
+        let mut expr = self.parse_single_expr();
+        while !self.scan(|t| t.as_arrow()) {
+            let mut args: Vec<Node> = vec![];
+            while !self.scan(|t| t.as_dot()) && !self.scan(|t| t.as_arrow()) {
+                args.push(self.parse_single_expr());
+            }
+            expr = Node::MethodCall(Box::new(expr), args);
+            if self.scan(|t| t.as_dot()) {
+                self.consume(|t| t.as_dot());
+            }
+        }
+        expr
+    }
+
+    fn is_alternative(&mut self) -> bool {
+        self.scan(|t| t.as_operator().filter(|op| op == "|"))
+    }
+
+    // p @ Point{x; y;}
+    fn parse_pattern(&mut self) -> Node {
+        let pattern = self.parse_single_pattern();
+        if self.scan(|t| t.as_at()) {
+            self.consume(|t| t.as_at());
+            let inner = self.parse_single_pattern();
+            Node::As(
+                self.bound_name(pattern),
+                Box::new(self.parse_alternatives(inner)),
+            )
+        } else {
+            self.parse_alternatives(pattern)
+        }
+    }
+
+    fn bound_name(&self, pattern: Node) -> String {
+        match pattern {
+            Node::IdLookup(name) => name,
+            _ => panic!("only a name can be bound with `@`"),
+        }
+    }
+
+    // ^true | ^false
+    fn parse_alternatives(&mut self, pattern: Node) -> Node {
+        if !self.is_alternative() {
+            return pattern;
+        }
+        let mut alternatives = vec![pattern];
+        while self.is_alternative() {
+            self.consume(|t| t.as_operator());
+            let alternative = self.parse_single_pattern();
+            // 1 | n @ [_;]
+            if self.scan(|t| t.as_at()) {
+                self.consume(|t| t.as_at());
+                let inner = self.parse_single_pattern();
+                alternatives.push(Node::As(self.bound_name(alternative), Box::new(inner)));
+            } else {
+                alternatives.push(alternative);
+            }
+        }
+        Node::Or(alternatives)
+    }
+
+    // the elements of a list, record or dict in a pattern are patterns too
+    fn parse_single_pattern(&mut self) -> Node {
+        if self.scan(|t| t.as_open_sq_brace()) {
+            self.parse_list(Self::parse_pattern_element)
+        } else if self.is_vector_constructor() {
+            self.parse_vector(Self::parse_pattern_element)
+        } else if self.scan(|t| t.as_open_brace()) {
+            self.parse_record(Self::parse_pattern_element)
+        } else if self.scan(|t| t.as_open_dict()) {
+            self.parse_dict_with(Self::parse_pattern_element)
+        } else if self.is_record_constructor() {
+            self.parse_constructor(Self::parse_pattern_element)
+        } else {
+            self.parse_single_expr()
+        }
+    }
+
+    // [1 | 2; x;]
+    fn parse_pattern_element(&mut self) -> Node {
+        let pattern = self.parse_pattern();
+        self.consume(|t| t.as_end_token());
+        pattern
     }
 
     fn parse_method(&mut self) -> Node {
-        self.consume(|t| t.as_def());
+        let position = self.consume(|t| match t {
+            Token::Def(idx) => Some(*idx),
+            _ => None,
+        });
         let mut args: Vec<Node> = vec![];
-        while !self.scan(|t| t.as_arrow()) {
-            args.push(self.parse_single_expr());
+        while !self.scan(|t| t.as_arrow()) && !self.is_guard() {
+            args.push(self.parse_pattern());
         }
+        let guard = if self.is_guard() {
+            Some(Box::new(self.parse_guard()))
+        } else {
+            None
+        };
         self.consume(|t| t.as_arrow());
         let body = self.parse_expr();
-        Node::Def(args, Box::new(body))
+        Node::Def(args, guard, Box::new(body), position)
     }
 
     fn parse_class(&mut self) -> Node {
         self.consume(|t| t.as_class());
         let name = self.consume(|t| t.as_id());
+        // class Bool = TrueClass | FalseClass;
+        let mut members: Vec<String> = vec![];
+        if self.scan(|t| t.as_operator().filter(|op| op == "=")) {
+            self.consume(|t| t.as_operator());
+            members.push(self.consume(|t| t.as_id()));
+            while self.is_alternative() {
+                self.consume(|t| t.as_operator());
+                members.push(self.consume(|t| t.as_id()));
+            }
+        }
+        // class Point{x; y;}
+        let mut fields: Option<Vec<String>> = None;
+        if self.scan(|t| t.as_open_brace()) {
+            self.consume(|t| t.as_open_brace());
+            let mut names = vec![];
+            while !self.scan(|t| t.as_close_brace()) {
+                names.push(self.consume(|t| t.as_id()));
+                self.consume(|t| t.as_end_token());
+            }
+            self.consume(|t| t.as_close_brace());
+            fields = Some(names);
+        }
         let mut methods: Vec<Node> = vec![];
         while !self.scan(|t| t.as_end_token()) {
             methods.push(self.parse_expr());
         }
-        Node::Class(name.clone(), methods)
+        Node::Class(name.clone(), members, fields, methods)
     }
 
     fn parse_keyword(&mut self) -> Node {